@@ -0,0 +1,299 @@
+use rstd::prelude::*;
+use runtime_primitives::traits::{Saturating, Zero};
+use support::dispatch::Result;
+use support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use system::ensure_root;
+
+/// A way to find the authority which authored the current block.
+pub trait FindAuthor<SessionKey> {
+    /// The author of the current block, if it is known.
+    fn find_author() -> Option<SessionKey>;
+}
+
+impl<SessionKey> FindAuthor<SessionKey> for () {
+    fn find_author() -> Option<SessionKey> {
+        None
+    }
+}
+
+/// A dead man's switch over each consensus authority. An authority must author a block at least
+/// every `MaxMissedBlocks` blocks, otherwise it is replaced by its backup key at the end of the
+/// block, so that a small permissioned network does not halt.
+pub trait Trait: consensus::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Finds the author of each block, which proves that its authority is still running.
+    type FindAuthor: FindAuthor<Self::SessionKey>;
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        <T as consensus::Trait>::SessionKey
+    {
+        AuthorityBackupSet(SessionKey, SessionKey),
+        AuthorityBackupRemoved(SessionKey),
+        AuthorityReplaced(SessionKey, SessionKey),
+    }
+);
+
+decl_storage! {
+    trait Store for Module<T: Trait> as AuthoritySwitch {
+        /// The key which replaces an authority
+        Backups get(backup) config(): map T::SessionKey => T::SessionKey;
+
+        /// The block number at which an authority last authored a block
+        LastAuthored get(last_authored) build(|config: &GenesisConfig<T>| config.backups.iter()
+            .map(|(authority, _)| (authority.clone(), T::BlockNumber::zero()))
+            .collect::<Vec<_>>()
+        ): map T::SessionKey => T::BlockNumber;
+
+        /// The number of blocks an authority can go without authoring, or zero to never replace authorities
+        MaxMissedBlocks get(max_missed_blocks) config(): T::BlockNumber;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+        fn deposit_event<T>() = default;
+
+        /// This call allows root to set the backup key of an authority.
+        pub fn set_backup(origin, authority: T::SessionKey, backup: T::SessionKey) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(authority != backup, "An authority cannot be its own backup");
+
+            <Backups<T>>::insert(&authority, backup.clone());
+            <LastAuthored<T>>::insert(&authority, <system::Module<T>>::block_number());
+
+            Self::deposit_event(RawEvent::AuthorityBackupSet(authority, backup));
+
+            Ok(())
+        }
+
+        /// This call allows root to stop an authority from being replaced.
+        pub fn remove_backup(origin, authority: T::SessionKey) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(<Backups<T>>::exists(&authority), "This authority does not have a backup");
+
+            <Backups<T>>::remove(&authority);
+            <LastAuthored<T>>::remove(&authority);
+
+            Self::deposit_event(RawEvent::AuthorityBackupRemoved(authority));
+
+            Ok(())
+        }
+
+        /// This call allows root to set the number of blocks an authority can go without authoring.
+        pub fn set_max_missed_blocks(origin, max_missed_blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <MaxMissedBlocks<T>>::put(max_missed_blocks);
+
+            Ok(())
+        }
+
+        fn on_finalize(n: T::BlockNumber) {
+            Self::record_author(n);
+            Self::replace_missing_authorities(n);
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Record that the author of the current block is still running, if it has a backup.
+    fn record_author(n: T::BlockNumber) {
+        if let Some(author) = T::FindAuthor::find_author() {
+            if <Backups<T>>::exists(&author) {
+                <LastAuthored<T>>::insert(&author, n);
+            }
+        }
+    }
+
+    /// Replace each authority which has not authored for more than `MaxMissedBlocks` blocks with
+    /// its backup key.
+    ///
+    /// The backup key starts with a fresh count, but has no backup of its own until root sets one.
+    fn replace_missing_authorities(n: T::BlockNumber) {
+        let max_missed_blocks = Self::max_missed_blocks();
+        if max_missed_blocks.is_zero() {
+            return;
+        }
+
+        let mut authorities = <consensus::Module<T>>::authorities();
+        let mut replaced = false;
+        for authority in authorities.iter_mut() {
+            if !<Backups<T>>::exists(&*authority) {
+                continue;
+            }
+            if n.saturating_sub(Self::last_authored(&*authority)) <= max_missed_blocks {
+                continue;
+            }
+
+            let backup = <Backups<T>>::take(&*authority);
+            <LastAuthored<T>>::remove(&*authority);
+            <LastAuthored<T>>::insert(&backup, n);
+
+            Self::deposit_event(RawEvent::AuthorityReplaced(authority.clone(), backup.clone()));
+            *authority = backup;
+            replaced = true;
+        }
+
+        if replaced {
+            <consensus::Module<T>>::set_authorities(&authorities);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use primitives::{Blake2Hasher, H256};
+    use runtime_io::with_externalities;
+    use runtime_primitives::{
+        testing::{Digest, DigestItem, Header, UintAuthorityId},
+        traits::{BlakeTwo256, IdentityLookup, OnFinalize},
+        BuildStorage,
+    };
+    use std::cell::Cell;
+    use support::{assert_noop, assert_ok, impl_outer_origin};
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl consensus::Trait for Test {
+        type SessionKey = UintAuthorityId;
+        type InherentOfflineReport = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type FindAuthor = TestAuthor;
+    }
+
+    thread_local! {
+        static AUTHOR: Cell<Option<u64>> = Cell::new(None);
+    }
+
+    /// A mock which finds the author set by a test.
+    pub struct TestAuthor;
+
+    impl TestAuthor {
+        fn set(author: u64) {
+            AUTHOR.with(|a| a.set(Some(author)));
+        }
+    }
+
+    impl FindAuthor<UintAuthorityId> for TestAuthor {
+        fn find_author() -> Option<UintAuthorityId> {
+            AUTHOR.with(|a| a.get()).map(UintAuthorityId)
+        }
+    }
+
+    type AuthoritySwitch = Module<Test>;
+    type Consensus = consensus::Module<Test>;
+    type System = system::Module<Test>;
+
+    fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .0;
+        t.extend(
+            consensus::GenesisConfig::<Test> {
+                code: vec![],
+                authorities: vec![UintAuthorityId(1), UintAuthorityId(2)],
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.extend(
+            GenesisConfig::<Test> {
+                backups: vec![(UintAuthorityId(1), UintAuthorityId(3))],
+                max_missed_blocks: 10,
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.into()
+    }
+
+    #[test]
+    fn authoring_should_keep_authority() {
+        with_externalities(&mut build_ext(), || {
+            assert_eq!(AuthoritySwitch::last_authored(UintAuthorityId(1)), 0);
+
+            TestAuthor::set(1);
+            AuthoritySwitch::on_finalize(5);
+            assert_eq!(AuthoritySwitch::last_authored(UintAuthorityId(1)), 5);
+
+            // check that an authority which authored recently is kept
+            AuthoritySwitch::on_finalize(15);
+            assert_eq!(Consensus::authorities(), vec![UintAuthorityId(1), UintAuthorityId(2)]);
+        });
+    }
+
+    #[test]
+    fn set_backup_should_work() {
+        with_externalities(&mut build_ext(), || {
+            System::set_block_number(5);
+            assert_ok!(AuthoritySwitch::set_backup(Origin::ROOT, UintAuthorityId(2), UintAuthorityId(4)));
+            assert_eq!(AuthoritySwitch::backup(UintAuthorityId(2)), UintAuthorityId(4));
+            assert_eq!(AuthoritySwitch::last_authored(UintAuthorityId(2)), 5);
+
+            assert_ok!(AuthoritySwitch::remove_backup(Origin::ROOT, UintAuthorityId(2)));
+            assert_eq!(<Backups<Test>>::exists(UintAuthorityId(2)), false);
+        });
+    }
+
+    #[test]
+    fn set_backup_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(
+                AuthoritySwitch::set_backup(Origin::ROOT, UintAuthorityId(2), UintAuthorityId(2)),
+                "An authority cannot be its own backup"
+            );
+            assert_noop!(
+                AuthoritySwitch::remove_backup(Origin::ROOT, UintAuthorityId(2)),
+                "This authority does not have a backup"
+            );
+        });
+    }
+
+    #[test]
+    fn missing_authority_should_be_replaced() {
+        with_externalities(&mut build_ext(), || {
+            AuthoritySwitch::on_finalize(10);
+            assert_eq!(Consensus::authorities(), vec![UintAuthorityId(1), UintAuthorityId(2)]);
+
+            // check that only the authority with a backup is replaced
+            TestAuthor::set(2);
+            AuthoritySwitch::on_finalize(11);
+            assert_eq!(Consensus::authorities(), vec![UintAuthorityId(3), UintAuthorityId(2)]);
+            assert_eq!(<Backups<Test>>::exists(UintAuthorityId(1)), false);
+            assert_eq!(AuthoritySwitch::last_authored(UintAuthorityId(3)), 11);
+        });
+    }
+}
@@ -1,14 +1,172 @@
-use super::BalancesCall;
-use parity_codec::{Decode, Encode};
-use runtime_primitives::traits::As;
+use parity_codec::{Codec, Decode, Encode};
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, CheckedAdd, CheckedMul, Hash as HashT, Saturating, StaticLookup, Verify, Zero};
+use runtime_primitives::transaction_validity::{
+    TransactionLongevity, TransactionPriority, TransactionValidity,
+};
+use runtime_primitives::ApplyError;
 use support::dispatch::{Dispatchable, Result};
-use support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
-use system::{ensure_signed, RawOrigin};
+use support::traits::{Currency, OnFreeBalanceZero, ReservableCurrency};
+use support::{decl_event, decl_module, decl_storage, ensure, Parameter, StorageMap, StorageValue};
+use system::{ensure_inherent, ensure_root, ensure_signed, RawOrigin};
+
+/// The transaction pool priority of liveness calls, so that they are never crowded out by other
+/// transactions in a congested pool.
+pub const LIVENESS_PRIORITY: TransactionPriority = TransactionPriority::max_value();
+
+/// The number of blocks a liveness call stays valid in the transaction pool.
+pub const LIVENESS_LONGEVITY: TransactionLongevity = TransactionLongevity::max_value();
+
+/// The number of recent ping tokens which are kept for a trustor with a duress commitment.
+const DURESS_PING_WINDOW: u64 = 100;
+
+/// The maximum number of contracts in a chain of beneficiaries through which an heir can inherit.
+const MAX_INHERITANCE_DEPTH: u32 = 3;
+
+/// The maximum number of destinations a contract can allow transfers to.
+const MAX_ALLOWED_DESTINATIONS: usize = 16;
+
+/// The maximum number of friends who can recover an account.
+const MAX_RECOVERY_FRIENDS: usize = 16;
+
+/// The number of most recent takeover calls which are kept in the audit log of a trustor.
+const AUDIT_LOG_LENGTH: u64 = 100;
+
+/// The unique identifier of a contract.
+pub type ContractId = u64;
+
+/// The calls which a beneficiary is allowed to make on behalf of a trustor.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum CallScope<Balance> {
+    /// Any call may be made.
+    All,
+    /// Only transfers of up to the given value may be made.
+    TransfersUpTo(Balance),
+}
+
+impl<Balance> Default for CallScope<Balance> {
+    fn default() -> Self {
+        CallScope::All
+    }
+}
+
+/// How a trustor's estate is handed over to the beneficiary once the switch fires.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Handover<BlockNumber> {
+    /// The beneficiary can act as the trustor through `act_as_trustor`.
+    Takeover,
+    /// The trustor's free balance is released to the beneficiary linearly over the given number
+    /// of blocks through `claim_vested`.
+    Vesting(BlockNumber),
+}
+
+impl<BlockNumber> Default for Handover<BlockNumber> {
+    fn default() -> Self {
+        Handover::Takeover
+    }
+}
+
+/// A schedule which releases a trustor's balance to their beneficiary after the switch fired.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VestingSchedule<Balance, BlockNumber> {
+    /// The balance which is released over the whole schedule.
+    total: Balance,
+    /// The balance which the beneficiary has claimed so far.
+    claimed: Balance,
+    /// The block number at which the schedule started.
+    starting_block: BlockNumber,
+    /// The number of blocks over which the total balance is released.
+    period: BlockNumber,
+}
+
+/// The outcome of a dispute over a contract, as voted by jurors.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Verdict<AccountId> {
+    /// The contract is deleted.
+    CancelContract,
+    /// The contract is kept as it is.
+    UpholdTakeover,
+    /// The contract is given to a new beneficiary.
+    ReassignBeneficiary(AccountId),
+}
+
+/// A dispute which a trustor or beneficiary opened over a contract.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Dispute<AccountId, Balance, BlockNumber> {
+    /// The party which opened the dispute.
+    challenger: AccountId,
+    /// The balance which the challenger reserved to open the dispute.
+    bond: Balance,
+    /// The block number after which jurors can no longer vote.
+    deadline: BlockNumber,
+    /// The number of juror votes for each verdict.
+    tally: Vec<(Verdict<AccountId>, u32)>,
+    /// The jurors who voted, so that their votes can be cleared once the dispute ends.
+    voters: Vec<AccountId>,
+}
+
+/// A call which a beneficiary made on behalf of a trustor.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AuditEntry<AccountId, BlockNumber, Hash> {
+    /// The block number at which the call was made.
+    pub block_number: BlockNumber,
+    /// The contract through which the call was made.
+    pub contract_id: ContractId,
+    /// The beneficiary which made the call.
+    pub beneficiary: AccountId,
+    /// The hash of the encoded call.
+    pub call_hash: Hash,
+    /// Whether the call succeeded.
+    pub succeeded: bool,
+}
+
+/// The friends who can recover an account whose key was lost.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RecoveryConfig<AccountId, BlockNumber> {
+    /// The accounts which can initiate and approve a recovery.
+    friends: Vec<AccountId>,
+    /// The number of friends who must approve a recovery.
+    threshold: u32,
+    /// The number of blocks after initiating a recovery during which the account can cancel it.
+    delay: BlockNumber,
+}
+
+/// A recovery which friends of an account have initiated.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ActiveRecovery<AccountId, BlockNumber> {
+    /// The account which will control the recovered account.
+    new_controller: AccountId,
+    /// The block number at which the recovery was initiated.
+    started: BlockNumber,
+    /// The friends who have approved the recovery.
+    approvals: Vec<AccountId>,
+}
+
+/// A takeover which a beneficiary initiated by reserving a bond.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Takeover<AccountId, Balance, BlockNumber> {
+    /// The beneficiary which initiated the takeover.
+    initiator: AccountId,
+    /// The balance which the initiator reserved, or zero once it was refunded.
+    bond: Balance,
+    /// The last block number at which the trustor can veto the takeover.
+    grace_end: BlockNumber,
+}
 
 /// Contract contains the necessary info for a user to specify a beneficiary to take over their account at a future time.
 ///
-/// Each user is allowed to specify a single `Contract` which defines when their account may be taken
-/// over if they are somehow incapacitated and cannot maintain their account.
+/// Each user is allowed to specify any number of `Contract`s which define when their account may be taken
+/// over if they are somehow incapacitated and cannot maintain their account. Every contract is
+/// identified by its own `ContractId`.
 ///
 /// When the `execution_block`
 /// number is reached, the `beneficiary` will be given access to the account. The original account
@@ -16,195 +174,650 @@ use system::{ensure_signed, RawOrigin};
 /// reset the `execution_block` value to be `block_delay` blocks beyond the current block.
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Contract<AccountId, BlockNumber> {
+pub struct Contract<AccountId, BlockNumber, Balance, Hash> {
+    /// The account which may be taken over.
+    trustor: AccountId,
     /// The account which will be given account take over privileges.
     beneficiary: AccountId,
     /// The number of blocks in the future that will be used each time a user pings that they are "alive".
     block_delay: BlockNumber,
     /// The block number at which the beneficiary is able to take over the account.
     execution_block: BlockNumber,
+    /// The calls which the beneficiary is allowed to make once the account is taken over.
+    scope: CallScope<Balance>,
+    /// How the account is handed over to the beneficiary.
+    handover: Handover<BlockNumber>,
+    /// Whether the trustor triggered the switch. Once a triggered contract is executable it can
+    /// no longer be pinged or changed, even with the trustor's key.
+    triggered: bool,
+    /// The block number until which the countdown of the contract is paused.
+    paused_until: BlockNumber,
+    /// Whether the beneficiary's own heirs can act as the trustor once the beneficiary's
+    /// contracts are executable too.
+    transitive: bool,
+    /// The only accounts the beneficiary can transfer to, or any account if empty.
+    allowed_destinations: Vec<AccountId>,
+    /// The hash of a secret which the beneficiary must reveal before taking over the account.
+    claim_commitment: Option<Hash>,
+}
+
+/// The balance type of a runtime's currency.
+type BalanceOf<T, I> = <<T as Trait<I>>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// The contract type of a runtime.
+type ContractOf<T, I> = Contract<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    BalanceOf<T, I>,
+    <T as system::Trait>::Hash,
+>;
+
+/// The dispute type of a runtime.
+type DisputeOf<T, I> = Dispute<
+    <T as system::Trait>::AccountId,
+    BalanceOf<T, I>,
+    <T as system::Trait>::BlockNumber,
+>;
+
+/// The takeover type of a runtime.
+type TakeoverOf<T, I> = Takeover<
+    <T as system::Trait>::AccountId,
+    BalanceOf<T, I>,
+    <T as system::Trait>::BlockNumber,
+>;
+
+/// A call which a beneficiary can make on behalf of a trustor, and which a contract's call scope
+/// and allowed destinations can inspect.
+pub trait TransferCall<AccountId, Balance> {
+    /// The value of the call if it is a transfer, or `None` otherwise.
+    fn transfer_value(&self) -> Option<Balance>;
+    /// The destination of the call if it is a transfer to a known account, or `None` otherwise.
+    fn transfer_dest(&self) -> Option<AccountId>;
+}
+
+impl<T: balances::Trait> TransferCall<T::AccountId, T::Balance> for balances::Call<T> {
+    fn transfer_value(&self) -> Option<T::Balance> {
+        match self {
+            balances::Call::transfer(_, value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn transfer_dest(&self) -> Option<T::AccountId> {
+        match self {
+            balances::Call::transfer(dest, _) => T::Lookup::lookup(dest.clone()).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// The transaction fee which is refunded for a fee exempt ping.
+pub trait PingFee<Balance> {
+    fn ping_fee() -> Balance;
+}
+
+impl<Balance: Zero> PingFee<Balance> for () {
+    fn ping_fee() -> Balance {
+        Zero::zero()
+    }
+}
+
+impl<T: balances::Trait> PingFee<T::Balance> for balances::Module<T> {
+    fn ping_fee() -> T::Balance {
+        Self::transaction_base_fee()
+    }
+}
+
+/// A handler for when a contract fires and its beneficiary takes over the trustor's account.
+pub trait OnSwitchFired<AccountId> {
+    /// A contract is known to be executable: a takeover was initiated, duress was claimed, root
+    /// executed it or vesting started.
+    fn on_switch_fired(contract_id: ContractId, trustor: &AccountId, beneficiary: &AccountId);
+    /// A beneficiary successfully made a call on behalf of a trustor.
+    fn on_takeover(contract_id: ContractId, trustor: &AccountId, beneficiary: &AccountId);
+}
+
+impl<AccountId> OnSwitchFired<AccountId> for () {
+    fn on_switch_fired(_: ContractId, _: &AccountId, _: &AccountId) {}
+    fn on_takeover(_: ContractId, _: &AccountId, _: &AccountId) {}
+}
+
+impl<AccountId, A: OnSwitchFired<AccountId>, B: OnSwitchFired<AccountId>> OnSwitchFired<AccountId> for (A, B) {
+    fn on_switch_fired(contract_id: ContractId, trustor: &AccountId, beneficiary: &AccountId) {
+        A::on_switch_fired(contract_id, trustor, beneficiary);
+        B::on_switch_fired(contract_id, trustor, beneficiary);
+    }
+    fn on_takeover(contract_id: ContractId, trustor: &AccountId, beneficiary: &AccountId) {
+        A::on_takeover(contract_id, trustor, beneficiary);
+        B::on_takeover(contract_id, trustor, beneficiary);
+    }
+}
+
+/// A handler for when a contract is changed or removed.
+pub trait OnContractChanged<AccountId> {
+    /// The beneficiary of a contract changed from `prev_beneficiary` to `beneficiary`.
+    fn on_beneficiary_changed(
+        contract_id: ContractId,
+        trustor: &AccountId,
+        prev_beneficiary: &AccountId,
+        beneficiary: &AccountId,
+    );
+    /// A contract was deleted.
+    fn on_contract_deleted(contract_id: ContractId, trustor: &AccountId);
+}
+
+impl<AccountId> OnContractChanged<AccountId> for () {
+    fn on_beneficiary_changed(_: ContractId, _: &AccountId, _: &AccountId, _: &AccountId) {}
+    fn on_contract_deleted(_: ContractId, _: &AccountId) {}
 }
 
-pub trait Trait: balances::Trait {
-    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+impl<AccountId, A: OnContractChanged<AccountId>, B: OnContractChanged<AccountId>> OnContractChanged<AccountId> for (A, B) {
+    fn on_beneficiary_changed(
+        contract_id: ContractId,
+        trustor: &AccountId,
+        prev_beneficiary: &AccountId,
+        beneficiary: &AccountId,
+    ) {
+        A::on_beneficiary_changed(contract_id, trustor, prev_beneficiary, beneficiary);
+        B::on_beneficiary_changed(contract_id, trustor, prev_beneficiary, beneficiary);
+    }
+    fn on_contract_deleted(contract_id: ContractId, trustor: &AccountId) {
+        A::on_contract_deleted(contract_id, trustor);
+        B::on_contract_deleted(contract_id, trustor);
+    }
+}
+
+/// The audit entry type of a runtime.
+type AuditEntryOf<T> = AuditEntry<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+pub trait Trait<I: Instance = DefaultInstance>: system::Trait {
+    type Event: From<Event<Self, I>> + Into<<Self as system::Trait>::Event>;
+    /// The currency which escrows, vesting and bonds are paid in.
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// The calls which a beneficiary can make on behalf of a trustor.
+    type TrustorCall: Parameter + Dispatchable<Origin = Self::Origin> + TransferCall<Self::AccountId, BalanceOf<Self, I>>;
+    /// The transaction fee which is refunded for a fee exempt ping.
+    type PingFee: PingFee<BalanceOf<Self, I>>;
+    /// The signature type used by trustors to sign liveness proofs.
+    type Signature: Parameter + Verify<Signer = Self::AccountId>;
+    /// A handler for when a contract fires or is taken over.
+    type OnSwitchFired: OnSwitchFired<Self::AccountId>;
+    /// A handler for when a contract's beneficiary changes or it is deleted.
+    type OnContractChanged: OnContractChanged<Self::AccountId>;
 }
 
 decl_event!(
-    pub enum Event<T>
+    pub enum Event<T, I: Instance = DefaultInstance>
     where
         <T as system::Trait>::AccountId,
-        <T as system::Trait>::BlockNumber
+        <T as system::Trait>::BlockNumber,
+        Balance = BalanceOf<T, I>
     {
-        ActedAsTrustor(AccountId, AccountId),
-        CreatedContract(AccountId, AccountId, BlockNumber),
-        BeneficiaryUpdated(AccountId, AccountId, AccountId),
-        BlockDelayUpdated(AccountId, BlockNumber, BlockNumber),
-        PingedAlive(AccountId, BlockNumber),
-        DeletedContract(AccountId),
+        ActedAsTrustor(ContractId, AccountId),
+        CreatedContract(ContractId, AccountId, AccountId, BlockNumber),
+        BeneficiaryUpdated(ContractId, AccountId, AccountId),
+        BlockDelayUpdated(ContractId, BlockNumber, BlockNumber),
+        CallScopeUpdated(ContractId),
+        HandoverUpdated(ContractId),
+        InheritanceUpdated(ContractId),
+        AllowedDestinationAdded(ContractId, AccountId),
+        AllowedDestinationRemoved(ContractId, AccountId),
+        ClaimCommitmentUpdated(ContractId),
+        ClaimSecretRevealed(ContractId, AccountId),
+        VestingStarted(ContractId, Balance),
+        VestedClaimed(ContractId, Balance),
+        EscrowDeposited(ContractId, Balance),
+        EscrowWithdrawn(ContractId, AccountId, Balance),
+        PingedAlive(ContractId, BlockNumber),
+        DeletedContract(ContractId),
+        PingDelegateAdded(AccountId, AccountId),
+        PingDelegateRemoved(AccountId, AccountId),
+        PingFeeRefunded(AccountId, Balance),
+        AttesterAdded(AccountId),
+        AttesterRemoved(AccountId),
+        DeathAttested(AccountId, AccountId),
+        AttestationRevoked(AccountId, AccountId),
+        AttestationQuorumReached(AccountId),
+        AttestationsVoided(AccountId),
+        SwitchTriggered(ContractId, BlockNumber),
+        DuressClaimed(ContractId),
+        ContractPaused(ContractId, AccountId, BlockNumber),
+        ContractForceCancelled(ContractId),
+        ContractForceExecuted(ContractId),
+        BeneficiaryForceSet(ContractId, AccountId, AccountId),
+        JurorAdded(AccountId),
+        JurorRemoved(AccountId),
+        DisputeOpened(ContractId, AccountId),
+        DisputeVoted(ContractId, AccountId),
+        DisputeResolved(ContractId, Option<Verdict<AccountId>>),
+        ContractsMigrated(AccountId, AccountId),
+        BeneficiaryReaped(ContractId, AccountId),
+        RecoveryConfigured(AccountId),
+        RecoveryInitiated(AccountId, AccountId),
+        RecoveryApproved(AccountId, AccountId),
+        RecoveryCancelled(AccountId),
+        AccountRecovered(AccountId, AccountId),
+        RecoveryRevoked(AccountId, AccountId),
+        ActedAsRecovered(AccountId, AccountId),
+        TakeoverInitiated(ContractId, AccountId, BlockNumber),
+        TakeoverVetoed(ContractId),
+        TakeoverBondSlashed(ContractId, Balance),
+        TakeoverBondRefunded(ContractId, Balance),
+        TriggerCancelled(ContractId),
     }
 );
 
 decl_storage! {
-    trait Store for Module<T: Trait> as DeadMansSwitchModule {
-        /// Fetch the dead man's switch contract for an account.
-        Contracts get(contract): map T::AccountId => Contract<T::AccountId, T::BlockNumber>;
+    trait Store for Module<T: Trait<I>, I: Instance = DefaultInstance> as DeadMansSwitchModule {
+        /// The id which will be given to the next contract.
+        NextContractId get(next_contract_id): ContractId;
+
+        /// Fetch a dead man's switch contract by its id.
+        Contracts get(contract): map ContractId => ContractOf<T, I>;
 
-        // The following "Trustors.." data structures are used to represent a list. This is
+        // The following "Contracts.." and "Trustors.." data structures are used to represent a list. This is
         // a common approach given the constraints of substrate
 
-        /// This representation of a list of trustors allows beneficiaries to lookup their trustors
-        TrustorsArray get(trustors_by_index): map (T::AccountId, u64) => T::AccountId;
-        /// The number of trustors a beneficiary has
+        /// This representation of a list of contracts allows trustors to lookup their contracts
+        ContractsArray get(contracts_by_index): map (T::AccountId, u64) => ContractId;
+        /// The number of contracts a trustor has
+        ContractsCount get(contracts_count): map T::AccountId => u64;
+        /// The array index of a trustor's contract
+        ContractsIndex get(contract_index): map ContractId => u64;
+
+        /// This representation of a list of trustor contracts allows beneficiaries to lookup their trustors
+        TrustorsArray get(trustors_by_index): map (T::AccountId, u64) => ContractId;
+        /// The number of trustor contracts a beneficiary has
         TrustorsCount get(trustors_count): map T::AccountId => u64;
-        /// The array index of a beneficiary's trustor
-        TrustorsIndex get(trustor_index): map T::AccountId => u64;
+        /// The array index of a beneficiary's trustor contract
+        TrustorsIndex get(trustor_index): map ContractId => u64;
+
+        /// The vesting schedule of a contract which releases funds to its beneficiary
+        VestingSchedules get(vesting_schedule): map ContractId => Option<VestingSchedule<BalanceOf<T, I>, T::BlockNumber>>;
+
+        /// The balance which a trustor has reserved into the escrow of a contract
+        Escrows get(escrow): map ContractId => BalanceOf<T, I>;
+
+        /// Whether an account (second key) is allowed to ping alive on behalf of a trustor (first key)
+        PingDelegates get(is_ping_delegate): map (T::AccountId, T::AccountId) => bool;
+        /// The number of ping delegates a trustor has
+        PingDelegatesCount get(ping_delegates_count): map T::AccountId => u32;
+
+        /// The nonce that a trustor's next liveness proof must be signed with
+        LivenessProofNonce get(liveness_proof_nonce): map T::AccountId => u64;
+
+        /// The nonce that an account's next consent to a migration must be signed with
+        MigrationNonce get(migration_nonce): map T::AccountId => u64;
 
         /// The minimum block delay for a contract
-        MinBlockDelay: T::BlockNumber = T::BlockNumber::sa(10);
+        MinBlockDelay get(min_block_delay) config(): T::BlockNumber = T::BlockNumber::sa(10);
 
         /// The maximum block delay for a contract
-        MaxBlockDelay: T::BlockNumber = T::BlockNumber::sa(1_000_000_000);
+        MaxBlockDelay get(max_block_delay) config(): T::BlockNumber = T::BlockNumber::sa(1_000_000_000);
+
+        /// The number of pings per period for which an account is refunded the transaction fee
+        FeeExemptPings: u32 = 1;
+
+        /// The length in blocks of a fee exemption period
+        FeeExemptPeriod: T::BlockNumber = T::BlockNumber::sa(14_400);
+
+        /// The last period in which an account was refunded a ping fee, and how many times
+        FeeExemptPingsUsed get(fee_exempt_pings_used): map T::AccountId => (T::BlockNumber, u32);
+
+        /// Whether an account is trusted to attest to the death of trustors
+        Attesters get(is_attester): map T::AccountId => bool;
+
+        /// The attesters who have attested to the death of a trustor
+        Attestations get(attestations): map T::AccountId => Vec<T::AccountId>;
+
+        /// Whether the attestations of a trustor's death have reached the quorum
+        QuorumReached get(quorum_reached): map T::AccountId => bool;
+
+        /// The number of attestations needed to shorten the contracts of a trustor
+        AttestationQuorum get(attestation_quorum): u32 = 2;
+
+        /// The number of blocks after a quorum of attestations at which a trustor's contracts become executable
+        AttestedBlockDelay get(attested_block_delay): T::BlockNumber = T::BlockNumber::sa(100);
+
+        /// The maximum number of blocks for which a contract can be paused
+        MaxPauseDuration get(max_pause_duration): T::BlockNumber = T::BlockNumber::sa(1_000_000);
+
+        /// Whether an account may vote on disputes
+        Jurors get(is_juror): map T::AccountId => bool;
+
+        /// The open dispute over a contract
+        Disputes get(dispute): map ContractId => Option<DisputeOf<T, I>>;
+
+        /// The verdict a juror voted for in the dispute over a contract
+        DisputeVotes get(dispute_vote): map (ContractId, T::AccountId) => Option<Verdict<T::AccountId>>;
+
+        /// The balance which must be reserved to open a dispute
+        DisputeBond get(dispute_bond): BalanceOf<T, I> = BalanceOf::<T, I>::sa(100);
+
+        /// The number of blocks for which jurors can vote on a dispute
+        DisputeVotingPeriod get(dispute_voting_period): T::BlockNumber = T::BlockNumber::sa(14_400);
+
+        /// The takeover which the beneficiary of a contract initiated
+        Takeovers get(takeover): map ContractId => Option<TakeoverOf<T, I>>;
+
+        /// The balance which must be reserved to initiate a takeover
+        TakeoverBond get(takeover_bond): BalanceOf<T, I> = BalanceOf::<T, I>::sa(100);
+
+        /// The number of blocks after initiating a takeover during which the trustor can veto it
+        TakeoverGracePeriod get(takeover_grace_period): T::BlockNumber = T::BlockNumber::sa(100);
+
+        /// The number of wrong claim secrets guessed for a contract, and the block number until
+        /// which no further guess is accepted
+        FailedClaims get(failed_claims): map ContractId => (u32, T::BlockNumber);
+
+        /// The number of blocks a beneficiary must wait after a wrong claim secret, for each wrong guess so far
+        ClaimRetryDelay get(claim_retry_delay): T::BlockNumber = T::BlockNumber::sa(100);
+
+        /// The number of calls which beneficiaries made on behalf of a trustor
+        AuditLogCount get(audit_log_count): map T::AccountId => u64;
+        /// The recent calls which beneficiaries made on behalf of a trustor
+        AuditLog get(audit_entry): map (T::AccountId, u64) => Option<AuditEntryOf<T>>;
+
+        /// The friends who can recover an account
+        RecoveryConfigs get(recovery_config): map T::AccountId => Option<RecoveryConfig<T::AccountId, T::BlockNumber>>;
+        /// The recovery which friends of an account have initiated
+        ActiveRecoveries get(active_recovery): map T::AccountId => Option<ActiveRecovery<T::AccountId, T::BlockNumber>>;
+        /// The account which controls a recovered account
+        RecoveryControllers get(recovery_controller): map T::AccountId => Option<T::AccountId>;
+
+        /// The hash of a trustor's duress secret
+        DuressCommitments get(duress_commitment): map T::AccountId => Option<T::Hash>;
+        /// The number of tokens a trustor with a duress commitment has pinged with
+        PingTokensCount get(ping_tokens_count): map T::AccountId => u64;
+        /// The token of each of a trustor's recent pings
+        PingTokens get(ping_token): map (T::AccountId, u64) => Option<T::Hash>;
+    }
+    add_extra_genesis {
+        config(phantom): rstd::marker::PhantomData<I>;
     }
 }
 
 decl_module! {
-    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+    pub struct Module<T: Trait<I>, I: Instance = DefaultInstance> for enum Call where origin: T::Origin {
 
-        fn deposit_event<T>() = default;
+        fn deposit_event<T, I>() = default;
 
         /// This call allows a user ("beneficiary") to act as another user ("trustor") in the event that
         /// the "trustor" is incapacitated.
-        pub fn act_as_trustor(origin, trustor: T::AccountId, call: BalancesCall<T>) -> Result {
+        ///
+        /// Unless the trustor triggered the contract, the beneficiary must first initiate a takeover
+        /// and wait for its grace period to end. The takeover bond is refunded by the first call.
+        ///
+        /// If the contract is transitive, the beneficiary's heirs can act as the trustor too.
+        pub fn act_as_trustor(origin, contract_id: ContractId, call: T::TrustorCall) -> Result {
             let sender = ensure_signed(origin)?;
 
-            ensure!(<Contracts<T>>::exists(&trustor), "You selected a trustor without a contract");
-            ensure!(sender != trustor, "You cannot act as yourself");
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
 
-            let contract = Self::contract(&trustor);
-            ensure!(contract.beneficiary == sender, "You are not the beneficiary for this trustor");
+            let contract = Self::contract(contract_id);
+            ensure!(sender != contract.trustor, "You cannot act as yourself");
+            Self::ensure_heir(&contract, &sender)?;
+
+            let current_block = <system::Module<T>>::block_number();
+            ensure!(contract.execution_block <= current_block, "You cannot act as this trustor yet");
+            ensure!(contract.handover == Handover::Takeover, "This contract releases funds through vesting");
+
+            Self::ensure_undisputed(contract_id)?;
+            Self::ensure_secret_claimed(&contract)?;
+
+            let takeover = Self::finished_takeover(contract_id, &contract)?;
+
+            Self::ensure_in_scope(&contract.scope, &call)?;
+            Self::ensure_allowed_destination(&contract.allowed_destinations, &call)?;
+
+            // Failed calls are logged too, so that attempts outside of the takeover are visible
+            let call_hash = T::Hashing::hash_of(&call);
+            let result = call.dispatch(RawOrigin::Signed(contract.trustor.clone()).into());
+            Self::record_audit_entry(&contract.trustor, contract_id, sender.clone(), call_hash, result.is_ok());
+            result?;
+
+            T::OnSwitchFired::on_takeover(contract_id, &contract.trustor, &sender);
+
+            Self::refund_takeover_bond(contract_id, takeover);
+
+            Self::deposit_event(RawEvent::ActedAsTrustor(contract_id, sender));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("beneficiary") to start taking over an executable contract by
+        /// reserving a bond.
+        ///
+        /// The trustor can veto the takeover or ping alive during the grace period, in which case the
+        /// bond is given to them.
+        pub fn initiate_takeover(origin, contract_id: ContractId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let contract = Self::contract(contract_id);
+            ensure!(sender != contract.trustor, "You cannot act as yourself");
+            Self::ensure_heir(&contract, &sender)?;
+            ensure!(!contract.triggered, "This contract was triggered and needs no takeover");
 
             let current_block = <system::Module<T>>::block_number();
             ensure!(contract.execution_block <= current_block, "You cannot act as this trustor yet");
+            ensure!(contract.handover == Handover::Takeover, "This contract releases funds through vesting");
+            ensure!(!<Takeovers<T, I>>::exists(contract_id), "A takeover of this contract was already initiated");
+            Self::ensure_undisputed(contract_id)?;
+
+            let bond = Self::takeover_bond();
+            T::Currency::reserve(&sender, bond)?;
+
+            let grace_end = current_block + Self::takeover_grace_period();
+            let takeover = Takeover {
+                initiator: sender.clone(),
+                bond,
+                grace_end,
+            };
+            <Takeovers<T, I>>::insert(contract_id, takeover);
+
+            T::OnSwitchFired::on_switch_fired(contract_id, &contract.trustor, &sender);
+
+            Self::deposit_event(RawEvent::TakeoverInitiated(contract_id, sender, grace_end));
+
+            Ok(())
+        }
 
-            call.dispatch(RawOrigin::Signed(trustor.clone()).into())?;
+        /// This call allows a user ("trustor") to veto a takeover of one of their contracts during
+        /// its grace period. The bond of the takeover is given to the trustor and the contract is
+        /// pushed back by its `block_delay`.
+        pub fn veto_takeover(origin, contract_id: ContractId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+
+            let takeover = Self::takeover(contract_id).ok_or("There is no takeover of this contract")?;
+            let current_block = <system::Module<T>>::block_number();
+            ensure!(current_block <= takeover.grace_end, "The grace period of this takeover is over");
+
+            Self::end_takeover(contract_id, Some(&sender))?;
+
+            current_contract.execution_block = current_block + current_contract.block_delay;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+
+            Self::deposit_event(RawEvent::TakeoverVetoed(contract_id));
+
+            Ok(())
+        }
 
-            Self::deposit_event(RawEvent::ActedAsTrustor(sender, trustor));
+        /// This call allows root to set the bond and grace period of new takeovers.
+        pub fn set_takeover_parameters(origin, bond: BalanceOf<T, I>, grace_period: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <TakeoverBond<T, I>>::put(bond);
+            <TakeoverGracePeriod<T, I>>::put(grace_period);
 
             Ok(())
         }
 
         /// This call allows a user ("trustor") to specify another user ("beneficiary") to take
         /// over their account in the event that they become incapacitated.
-        pub fn create_contract(origin, beneficiary: T::AccountId, block_delay: T::BlockNumber) -> Result {
+        pub fn create_contract(origin, beneficiary: T::AccountId, block_delay: T::BlockNumber, scope: CallScope<BalanceOf<T, I>>) -> Result {
             let sender = ensure_signed(origin)?;
 
-            ensure!(!<Contracts<T>>::exists(&sender), "You can only have one contract");
             ensure!(sender != beneficiary, "You cannot use yourself as your beneficiary");
 
-            let min_block_delay = <MinBlockDelay<T>>::get();
+            let min_block_delay = <MinBlockDelay<T, I>>::get();
             ensure!(block_delay >= min_block_delay, "Your block delay is too short");
 
-            let max_block_delay = <MaxBlockDelay<T>>::get();
+            let max_block_delay = <MaxBlockDelay<T, I>>::get();
             ensure!(block_delay <= max_block_delay, "Your block delay is too long");
 
-            let trustors_count = Self::trustors_count(&beneficiary);
-            let new_trustors_count = trustors_count.checked_add(1)
-                .ok_or("Overflow adding a new trustor for this beneficiary")?;
+            let contract_id = Self::next_contract_id();
+            let next_contract_id = contract_id.checked_add(1)
+                .ok_or("Overflow creating a new contract")?;
 
             let current_block = <system::Module<T>>::block_number();
             let execution_block = current_block + block_delay;
             let contract = Contract {
+                trustor: sender.clone(),
                 beneficiary: beneficiary.clone(),
                 block_delay,
                 execution_block,
+                scope,
+                handover: Handover::Takeover,
+                triggered: false,
+                paused_until: Zero::zero(),
+                transitive: false,
+                allowed_destinations: Vec::new(),
+                claim_commitment: None,
             };
-            <Contracts<T>>::insert(&sender, &contract);
 
-            <TrustorsArray<T>>::insert((beneficiary.clone(), trustors_count), &sender);
-            <TrustorsCount<T>>::insert(&beneficiary, new_trustors_count);
-            <TrustorsIndex<T>>::insert(&sender, trustors_count);
+            Self::add_contract(&sender, contract_id)?;
+            Self::add_trustor(&beneficiary, contract_id)?;
+
+            <Contracts<T, I>>::insert(contract_id, &contract);
+            <NextContractId<T, I>>::put(next_contract_id);
 
-            Self::deposit_event(RawEvent::CreatedContract(sender, beneficiary, block_delay));
+            Self::deposit_event(RawEvent::CreatedContract(contract_id, sender, beneficiary, block_delay));
 
             Ok(())
         }
 
-        /// This call allows a user ("trustor") to delete their contract.
-        pub fn delete_contract(origin) -> Result {
+        /// This call allows a user ("trustor") to delete one of their contracts.
+        pub fn delete_contract(origin, contract_id: ContractId) -> Result {
             let sender = ensure_signed(origin)?;
 
-            ensure!(<Contracts<T>>::exists(&sender), "You do not have a current contract");
-            ensure!(<TrustorsIndex<T>>::exists(&sender), "Your account is in a bad state");
+            let current_contract = Self::trustor_contract(&sender, contract_id)?;
 
-            let current_contract = Self::contract(&sender);
-            let beneficiary = current_contract.beneficiary;
+            Self::do_delete_contract(contract_id, &current_contract)?;
 
-            let trustors_count = Self::trustors_count(&beneficiary);
-            let new_trustors_count = trustors_count.checked_sub(1)
-                .ok_or("Underflow remove a trustor for this beneficiary")?;
+            Self::deposit_event(RawEvent::DeletedContract(contract_id));
 
-            <Contracts<T>>::remove(&sender);
+            Ok(())
+        }
 
-            // Prepare to remove the last trustor from the beneficiary's list
-            let mut trustor_index = <TrustorsIndex<T>>::get(&sender);
-            if trustor_index != new_trustors_count {
-                let last_trustor_id = <TrustorsArray<T>>::get((beneficiary.clone(), new_trustors_count));
-                <TrustorsArray<T>>::insert((beneficiary.clone(), trustor_index), &last_trustor_id);
-                <TrustorsIndex<T>>::insert(last_trustor_id, trustor_index);
-                trustor_index = new_trustors_count;
-            }
+        /// This call allows a user ("trustor") who moves to a new key to move all of their contracts
+        /// to `new_account`, which consents by signing the migration with its `migration_nonce`.
+        ///
+        /// The contracts keep their ids, so beneficiaries' lists of trustors are unchanged. Escrows
+        /// are reserved from the new account, and `move_balance` also transfers the trustor's
+        /// free balance to it, except for the existential deposit. The migration counts as a ping
+        /// from the new account.
+        ///
+        /// Ping delegates and duress commitments are tied to the old account, so they must be
+        /// removed first and set up again from the new account.
+        pub fn migrate_contract(origin, new_account: T::AccountId, signature: T::Signature, move_balance: bool) -> Result {
+            let sender = ensure_signed(origin)?;
 
-            <TrustorsArray<T>>::remove((beneficiary.clone(), trustor_index));
-            <TrustorsCount<T>>::insert(&beneficiary, new_trustors_count);
-            <TrustorsIndex<T>>::remove(&sender);
+            ensure!(sender != new_account, "You cannot migrate to your own account");
+
+            let contracts_count = Self::contracts_count(&sender);
+            ensure!(contracts_count > 0, "You do not have a current contract");
+            ensure!(
+                Self::verify_migration(&sender, &new_account, &signature),
+                "Your migration signature is invalid"
+            );
+            let next_migration_nonce = Self::migration_nonce(&new_account).checked_add(1)
+                .ok_or("Overflow incrementing the migration nonce")?;
+            ensure!(Self::ping_delegates_count(&sender) == 0, "Remove your ping delegates before migrating");
+            ensure!(
+                Self::duress_commitment(&sender).is_none(),
+                "Remove your duress commitment before migrating"
+            );
 
-            Self::deposit_event(RawEvent::DeletedContract(sender));
+            let contract_ids: Vec<ContractId> = (0..contracts_count)
+                .map(|index| Self::contracts_by_index((sender.clone(), index)))
+                .collect();
 
-            Ok(())
-        }
+            let mut escrow: BalanceOf<T, I> = Zero::zero();
+            for &contract_id in &contract_ids {
+                let contract = Self::trustor_contract(&sender, contract_id)?;
+                ensure!(
+                    contract.beneficiary != new_account,
+                    "Your new account is the beneficiary of one of your contracts"
+                );
+                Self::ensure_undisputed(contract_id)?;
+                escrow = escrow.checked_add(&Self::escrow(contract_id))
+                    .ok_or("Overflow adding up the escrow of your contracts")?;
+            }
+            if !move_balance {
+                ensure!(
+                    T::Currency::free_balance(&new_account) >= escrow,
+                    "Your new account cannot cover the escrow of your contracts"
+                );
+            }
 
+            // Every fallible currency operation comes before the contracts are touched, and a
+            // failed transfer reserves the escrow on the old account again. The existential
+            // deposit stays behind, so that the old account is not reaped along with its contracts.
+            if move_balance {
+                T::Currency::unreserve(&sender, escrow);
+                let balance = T::Currency::free_balance(&sender).saturating_sub(T::Currency::minimum_balance());
+                if let Err(err) = T::Currency::transfer(&sender, &new_account, balance) {
+                    T::Currency::reserve(&sender, escrow)?;
+                    return Err(err);
+                }
+                T::Currency::reserve(&new_account, escrow)?;
+            } else {
+                T::Currency::reserve(&new_account, escrow)?;
+                T::Currency::unreserve(&sender, escrow);
+            }
 
-        /// This call allows a user ("trustor") to specify a new "beneficiary".
-        pub fn update_beneficiary(origin, beneficiary: T::AccountId) -> Result {
-            let sender = ensure_signed(origin)?;
+            for &contract_id in &contract_ids {
+                let mut contract = Self::contract(contract_id);
+                Self::remove_contract(&sender, contract_id)?;
+                Self::add_contract(&new_account, contract_id)?;
+                contract.trustor = new_account.clone();
+                <Contracts<T, I>>::insert(contract_id, &contract);
+            }
 
-            ensure!(<Contracts<T>>::exists(&sender), "You do not have a current contract");
-            ensure!(sender != beneficiary, "You cannot use yourself as your beneficiary");
-            ensure!(<TrustorsIndex<T>>::exists(&sender), "Your account is in a bad state");
+            <MigrationNonce<T, I>>::insert(&new_account, next_migration_nonce);
 
-            let mut current_contract = Self::contract(&sender);
-            let prev_beneficiary = current_contract.beneficiary;
-            ensure!(prev_beneficiary != beneficiary, "Your beneficiary is already set to this account");
+            // Liveness proofs are signed by the new key from now on, so its nonce must not go back
+            let nonce = Self::liveness_proof_nonce(&sender).max(Self::liveness_proof_nonce(&new_account));
+            <LivenessProofNonce<T, I>>::insert(&new_account, nonce);
 
-            let trustors_count = Self::trustors_count(&beneficiary);
-            let trustors_index = trustors_count;
-            let new_trustors_count = trustors_count.checked_add(1)
-                .ok_or("Overflow adding a new trustor for this beneficiary")?;
+            <Attestations<T, I>>::remove(&sender);
+            <QuorumReached<T, I>>::remove(&sender);
+            Self::do_ping_alive(new_account.clone())?;
 
-            let prev_beneficiary_trustors_count = Self::trustors_count(&prev_beneficiary);
-            let new_prev_beneficiary_trustors_count = prev_beneficiary_trustors_count.checked_sub(1)
-                .ok_or("Underflow removing trustor for previous beneficiary")?;
+            Self::deposit_event(RawEvent::ContractsMigrated(sender, new_account));
 
-            current_contract.beneficiary = beneficiary.clone();
-            <Contracts<T>>::insert(&sender, &current_contract);
+            Ok(())
+        }
 
-            // Prepare to remove the last trustor from the previous beneficiary's list
-            let mut prev_trustor_index = <TrustorsIndex<T>>::get(&sender);
-            if prev_trustor_index != new_prev_beneficiary_trustors_count {
-                let last_trustor_id = <TrustorsArray<T>>::get((prev_beneficiary.clone(), new_prev_beneficiary_trustors_count));
-                <TrustorsArray<T>>::insert((prev_beneficiary.clone(), prev_trustor_index), &last_trustor_id);
-                <TrustorsIndex<T>>::insert(last_trustor_id, prev_trustor_index);
-                prev_trustor_index = new_prev_beneficiary_trustors_count;
-            }
+        /// This call allows a user ("trustor") to specify a new "beneficiary" for one of their contracts.
+        pub fn update_beneficiary(origin, contract_id: ContractId, beneficiary: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
 
-            <TrustorsIndex<T>>::insert(&sender, trustors_index);
-            <TrustorsArray<T>>::remove((prev_beneficiary.clone(), prev_trustor_index));
-            <TrustorsArray<T>>::insert((beneficiary.clone(), trustors_index), &sender);
+            let current_contract = Self::trustor_contract(&sender, contract_id)?;
+            Self::ensure_undisputed(contract_id)?;
+            let prev_beneficiary = current_contract.beneficiary.clone();
 
-            <TrustorsCount<T>>::insert(&prev_beneficiary, new_prev_beneficiary_trustors_count);
-            <TrustorsCount<T>>::insert(&beneficiary, new_trustors_count);
+            Self::do_update_beneficiary(contract_id, current_contract, beneficiary.clone())?;
 
-            Self::deposit_event(RawEvent::BeneficiaryUpdated(sender, prev_beneficiary, beneficiary));
+            Self::deposit_event(RawEvent::BeneficiaryUpdated(contract_id, prev_beneficiary, beneficiary));
 
             Ok(())
         }
@@ -214,369 +827,3845 @@ decl_module! {
         ///
         /// A side effect of this call is that the `execution_block` will be updated to correspond with
         /// the `block_delay`.
-        pub fn update_block_delay(origin, block_delay: T::BlockNumber) -> Result {
+        pub fn update_block_delay(origin, contract_id: ContractId, block_delay: T::BlockNumber) -> Result {
             let sender = ensure_signed(origin)?;
 
-            ensure!(<Contracts<T>>::exists(&sender), "You do not have a current contract");
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
 
-            let min_block_delay = <MinBlockDelay<T>>::get();
+            let min_block_delay = <MinBlockDelay<T, I>>::get();
             ensure!(block_delay >= min_block_delay, "Your block delay is too short");
 
-            let max_block_delay = <MaxBlockDelay<T>>::get();
+            let max_block_delay = <MaxBlockDelay<T, I>>::get();
             ensure!(block_delay <= max_block_delay, "Your block delay is too long");
 
             let current_block = <system::Module<T>>::block_number();
             let execution_block = current_block + block_delay;
 
-            let mut current_contract = Self::contract(&sender);
             let prev_block_delay = current_contract.block_delay;
             current_contract.block_delay = block_delay.clone();
             current_contract.execution_block = execution_block.clone();
-            <Contracts<T>>::insert(&sender, &current_contract);
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
 
-            Self::deposit_event(RawEvent::BlockDelayUpdated(sender, prev_block_delay, block_delay));
+            Self::deposit_event(RawEvent::BlockDelayUpdated(contract_id, prev_block_delay, block_delay));
 
             Ok(())
         }
 
-        /// This call allows a user ("trustor") to prolong the `execution_block` time.
-        pub fn ping_alive(origin) -> Result {
+        /// This call allows a user ("trustor") to change which calls the beneficiary of one of
+        /// their contracts will be allowed to make.
+        pub fn update_call_scope(origin, contract_id: ContractId, scope: CallScope<BalanceOf<T, I>>) -> Result {
             let sender = ensure_signed(origin)?;
 
-            ensure!(<Contracts<T>>::exists(&sender), "You do not have a current contract");
-
-            let mut current_contract = Self::contract(&sender);
-            let current_block = <system::Module<T>>::block_number();
-            let execution_block = current_block + current_contract.block_delay;
-            current_contract.execution_block = execution_block.clone();
-            <Contracts<T>>::insert(&sender, &current_contract);
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            Self::ensure_undisputed(contract_id)?;
+            current_contract.scope = scope;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
 
-            Self::deposit_event(RawEvent::PingedAlive(sender, execution_block));
+            Self::deposit_event(RawEvent::CallScopeUpdated(contract_id));
 
             Ok(())
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        /// This call allows a user ("trustor") to let the heirs of the beneficiary of one of their
+        /// contracts act as the trustor, once the beneficiary's own contracts are executable too.
+        pub fn update_inheritance(origin, contract_id: ContractId, transitive: bool) -> Result {
+            let sender = ensure_signed(origin)?;
 
-    use primitives::{Blake2Hasher, H256};
-    use runtime_io::with_externalities;
-    use runtime_primitives::{
-        testing::{Digest, DigestItem, Header},
-        traits::{BlakeTwo256, IdentityLookup},
-        BuildStorage,
-    };
-    use support::{assert_noop, assert_ok, impl_outer_origin};
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            current_contract.transitive = transitive;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
 
-    impl_outer_origin! {
-        pub enum Origin for Test {}
-    }
+            Self::deposit_event(RawEvent::InheritanceUpdated(contract_id));
 
-    // For testing the module, we construct most of a mock runtime. This means
-    // first constructing a configuration type (`Test`) which `impl`s each of the
-    // configuration traits of modules we want to use.
-    #[derive(Clone, Eq, PartialEq)]
-    pub struct Test;
-    impl system::Trait for Test {
-        type Origin = Origin;
-        type Index = u64;
-        type BlockNumber = u64;
-        type Hash = H256;
-        type Hashing = BlakeTwo256;
-        type Digest = Digest;
-        type AccountId = u64;
-        type Lookup = IdentityLookup<Self::AccountId>;
-        type Header = Header;
-        type Event = ();
-        type Log = DigestItem;
-    }
+            Ok(())
+        }
 
-    impl balances::Trait for Test {
-        type Balance = u64;
-        type OnFreeBalanceZero = ();
-        type OnNewAccount = ();
-        type Event = ();
-        type TransactionPayment = ();
-        type TransferPayment = ();
-        type DustRemoval = ();
-    }
+        /// This call allows a user ("trustor") to add an account, such as a family trust, to the
+        /// accounts which the beneficiary of one of their contracts can transfer to.
+        ///
+        /// Once a contract has allowed destinations, the beneficiary can only make transfers to them.
+        pub fn add_allowed_destination(origin, contract_id: ContractId, destination: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
 
-    impl Trait for Test {
-        type Event = ();
-    }
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            ensure!(
+                !current_contract.allowed_destinations.contains(&destination),
+                "This destination is already allowed"
+            );
+            ensure!(
+                current_contract.allowed_destinations.len() < MAX_ALLOWED_DESTINATIONS,
+                "Your contract has too many allowed destinations"
+            );
 
-    type DMS = Module<Test>;
-    type System = system::Module<Test>;
+            current_contract.allowed_destinations.push(destination.clone());
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
 
-    fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-        let mut t = system::GenesisConfig::<Test>::default()
-            .build_storage()
-            .unwrap()
-            .0;
-        t.extend(
-            balances::GenesisConfig::<Test> {
-                balances: vec![(1, 50), (2, 100)],
-                vesting: Default::default(),
-                existential_deposit: Default::default(),
-                creation_fee: Default::default(),
-                transaction_base_fee: Default::default(),
-                transaction_byte_fee: Default::default(),
-                transfer_fee: Default::default(),
-            }
-            .build_storage()
-            .unwrap()
-            .0,
-        );
-        t.into()
-    }
+            Self::deposit_event(RawEvent::AllowedDestinationAdded(contract_id, destination));
 
-    #[test]
-    fn act_as_trustor_should_work() {
-        with_externalities(&mut build_ext(), || {
-            // create a contract to give access to account #2 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10));
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to remove an account from the allowed destinations
+        /// of one of their contracts.
+        pub fn remove_allowed_destination(origin, contract_id: ContractId, destination: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            let position = current_contract.allowed_destinations.iter().position(|allowed| allowed == &destination)
+                .ok_or("This destination is not allowed")?;
+
+            current_contract.allowed_destinations.swap_remove(position);
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+
+            Self::deposit_event(RawEvent::AllowedDestinationRemoved(contract_id, destination));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to commit to the hash of a claim secret, which they
+        /// share off-chain with the beneficiary of one of their contracts, or to remove it.
+        ///
+        /// Once set, the beneficiary must reveal the secret through `claim_with_secret` before they
+        /// can take over the account, so that a stolen beneficiary key is not enough on its own.
+        pub fn update_claim_commitment(origin, contract_id: ContractId, commitment: Option<T::Hash>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            current_contract.claim_commitment = commitment;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+            <FailedClaims<T, I>>::remove(contract_id);
+
+            Self::deposit_event(RawEvent::ClaimCommitmentUpdated(contract_id));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("beneficiary") to reveal the claim secret of an executable
+        /// contract.
+        ///
+        /// Each wrong secret locks out further guesses for `claim_retry_delay` blocks times the
+        /// number of wrong secrets so far.
+        ///
+        /// The secret is public once revealed, so the commitment is cleared. A trustor who pings
+        /// alive again must commit to a new secret to keep their contract protected.
+        pub fn claim_with_secret(origin, contract_id: ContractId, secret: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let mut contract = Self::contract(contract_id);
+            ensure!(sender != contract.trustor, "You cannot act as yourself");
+            Self::ensure_heir(&contract, &sender)?;
+
+            let current_block = <system::Module<T>>::block_number();
+            ensure!(contract.execution_block <= current_block, "You cannot act as this trustor yet");
+
+            let commitment = contract.claim_commitment.ok_or("This contract does not have a claim secret")?;
+
+            let (failures, locked_until) = Self::failed_claims(contract_id);
+            ensure!(locked_until <= current_block, "You must wait before guessing the claim secret again");
+
+            if T::Hashing::hash_of(&secret) != commitment {
+                let failures = failures.saturating_add(1);
+                let locked_until = current_block + Self::claim_retry_delay() * T::BlockNumber::sa(failures as u64);
+                <FailedClaims<T, I>>::insert(contract_id, (failures, locked_until));
+                return Err("Your claim secret is invalid");
+            }
+
+            contract.claim_commitment = None;
+            <Contracts<T, I>>::insert(contract_id, &contract);
+            <FailedClaims<T, I>>::remove(contract_id);
+
+            Self::deposit_event(RawEvent::ClaimSecretRevealed(contract_id, sender));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to choose how one of their contracts hands over
+        /// their account once it is executable.
+        pub fn update_handover(origin, contract_id: ContractId, handover: Handover<T::BlockNumber>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            if let Handover::Vesting(period) = handover {
+                ensure!(!period.is_zero(), "Your vesting period is too short");
+            }
+
+            current_contract.handover = handover;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+
+            Self::deposit_event(RawEvent::HandoverUpdated(contract_id));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("beneficiary") to claim the part of a trustor's balance which
+        /// has vested since a vesting contract became executable.
+        ///
+        /// The vesting schedule is started by the first claim, and covers the trustor's free balance
+        /// at that time. The claim is paid to `dest`, which must be one of the contract's allowed
+        /// destinations if it has any.
+        pub fn claim_vested(origin, contract_id: ContractId, dest: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let contract = Self::contract(contract_id);
+            ensure!(contract.beneficiary == sender, "You are not the beneficiary for this trustor");
+
+            let period = match contract.handover {
+                Handover::Vesting(period) => period,
+                Handover::Takeover => return Err("This contract does not release funds through vesting"),
+            };
+
+            let current_block = <system::Module<T>>::block_number();
+            ensure!(contract.execution_block <= current_block, "You cannot act as this trustor yet");
+            Self::ensure_undisputed(contract_id)?;
+            Self::ensure_secret_claimed(&contract)?;
+            Self::ensure_allowed_payout(&contract.allowed_destinations, &dest)?;
+
+            let (mut schedule, started) = match Self::vesting_schedule(contract_id) {
+                Some(schedule) => (schedule, false),
+                None => {
+                    let schedule = VestingSchedule {
+                        total: T::Currency::free_balance(&contract.trustor),
+                        claimed: Zero::zero(),
+                        starting_block: contract.execution_block,
+                        period,
+                    };
+                    (schedule, true)
+                }
+            };
+
+            let vested = Self::vested_balance(&schedule, current_block)?;
+            let claimable = vested.saturating_sub(schedule.claimed);
+            ensure!(!claimable.is_zero(), "There is no vested balance to claim");
+
+            // The schedule is stored before the transfer, which may reap the trustor's account,
+            // so that the contract is kept as handed over
+            let prev_claimed = schedule.claimed;
+            schedule.claimed = schedule.claimed + claimable;
+            let total = schedule.total;
+            <VestingSchedules<T, I>>::insert(contract_id, &schedule);
+
+            if let Err(err) = T::Currency::transfer(&contract.trustor, &dest, claimable) {
+                if started {
+                    <VestingSchedules<T, I>>::remove(contract_id);
+                } else {
+                    schedule.claimed = prev_claimed;
+                    <VestingSchedules<T, I>>::insert(contract_id, schedule);
+                }
+                return Err(err);
+            }
+
+            if started {
+                T::OnSwitchFired::on_switch_fired(contract_id, &contract.trustor, &sender);
+                Self::deposit_event(RawEvent::VestingStarted(contract_id, total));
+            }
+            Self::deposit_event(RawEvent::VestedClaimed(contract_id, claimable));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to lock some of their balance into the escrow of one
+        /// of their contracts, so that it is set aside for the beneficiary.
+        pub fn deposit_escrow(origin, contract_id: ContractId, value: BalanceOf<T, I>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            Self::trustor_contract(&sender, contract_id)?;
+
+            let escrow = Self::escrow(contract_id);
+            let new_escrow = escrow.checked_add(&value)
+                .ok_or("Overflow adding to the escrow of this contract")?;
+
+            T::Currency::reserve(&sender, value)?;
+            <Escrows<T, I>>::insert(contract_id, new_escrow);
+
+            Self::deposit_event(RawEvent::EscrowDeposited(contract_id, value));
+
+            Ok(())
+        }
+
+        /// This call allows a user to withdraw from the escrow of a contract. The trustor can
+        /// withdraw it back into their free balance until the contract is executable, after which
+        /// only the beneficiary can withdraw it to `dest`, which must be one of the contract's
+        /// allowed destinations if it has any.
+        ///
+        /// Unless the trustor triggered the contract, the beneficiary must first wait out the grace
+        /// period of a takeover, just like for `act_as_trustor`.
+        pub fn withdraw_escrow(origin, contract_id: ContractId, dest: T::AccountId, value: BalanceOf<T, I>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let contract = Self::contract(contract_id);
+            let escrow = Self::escrow(contract_id);
+            ensure!(value <= escrow, "The escrow of this contract is too low");
+
+            let current_block = <system::Module<T>>::block_number();
+            if sender == contract.trustor {
+                ensure!(current_block < contract.execution_block, "Your contract is already executable");
+                ensure!(dest == sender, "You can only withdraw the escrow into your own account");
+                T::Currency::unreserve(&sender, value);
+            } else if sender == contract.beneficiary {
+                ensure!(contract.execution_block <= current_block, "You cannot act as this trustor yet");
+                Self::ensure_undisputed(contract_id)?;
+                Self::ensure_secret_claimed(&contract)?;
+                Self::ensure_allowed_payout(&contract.allowed_destinations, &dest)?;
+                let takeover = Self::finished_takeover(contract_id, &contract)?;
+                T::Currency::repatriate_reserved(&contract.trustor, &dest, value)?;
+                Self::refund_takeover_bond(contract_id, takeover);
+            } else {
+                return Err("You are not a party to this contract");
+            }
+
+            <Escrows<T, I>>::insert(contract_id, escrow - value);
+
+            Self::deposit_event(RawEvent::EscrowWithdrawn(contract_id, sender, value));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to make one of their contracts executable right away,
+        /// for example when they know their key is compromised.
+        ///
+        /// The contract becomes executable after `confirmation_delay` blocks, during which the
+        /// trustor can still cancel the trigger by pinging alive. After that, the contract can no
+        /// longer be pinged or changed.
+        pub fn trigger_now(origin, contract_id: ContractId, confirmation_delay: T::BlockNumber) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            ensure!(!current_contract.triggered, "Your contract was already triggered");
+
+            let execution_block = <system::Module<T>>::block_number() + confirmation_delay;
+            if execution_block < current_contract.execution_block {
+                current_contract.execution_block = execution_block;
+            }
+            current_contract.triggered = true;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+
+            Self::deposit_event(RawEvent::SwitchTriggered(contract_id, current_contract.execution_block));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to pause the countdown of one of their contracts
+        /// until the given block, for example during a planned absence.
+        ///
+        /// The blocks left before the contract becomes executable are kept, and the countdown
+        /// resumes by itself once the pause ends. Pinging alive during the pause cannot bring the
+        /// contract forward.
+        pub fn pause_contract(origin, contract_id: ContractId, until_block: T::BlockNumber) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut current_contract = Self::trustor_contract(&sender, contract_id)?;
+            ensure!(!current_contract.triggered, "Your contract was triggered");
+
+            let current_block = <system::Module<T>>::block_number();
+            ensure!(current_block >= current_contract.paused_until, "Your contract is already paused");
+            ensure!(until_block > current_block, "Your pause must end in the future");
+            ensure!(until_block - current_block <= Self::max_pause_duration(), "Your pause is too long");
+
+            // The execution block of a contract paused before already includes that pause
+            let paused_from = current_block.max(current_contract.paused_until);
+            let remaining_blocks = current_contract.execution_block.saturating_sub(paused_from);
+            current_contract.execution_block = until_block + remaining_blocks;
+            current_contract.paused_until = until_block;
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+
+            Self::deposit_event(RawEvent::ContractPaused(contract_id, current_contract.beneficiary, until_block));
+
+            Ok(())
+        }
+
+        /// This call allows root to set the maximum number of blocks for which a contract can be paused.
+        pub fn set_max_pause_duration(origin, duration: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <MaxPauseDuration<T, I>>::put(duration);
+
+            Ok(())
+        }
+
+        /// This call allows root (e.g. following a court order) to cancel a contract, for example
+        /// after a wrongful takeover. Any escrow is returned to the trustor.
+        pub fn force_cancel_contract(origin, contract_id: ContractId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let contract = Self::contract(contract_id);
+            Self::do_delete_contract(contract_id, &contract)?;
+
+            Self::deposit_event(RawEvent::ContractForceCancelled(contract_id));
+
+            Ok(())
+        }
+
+        /// This call allows root (e.g. following a court order) to make a contract executable
+        /// right away. Like a confirmed `trigger_now`, it can no longer be pinged back.
+        pub fn force_execute_contract(origin, contract_id: ContractId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let mut contract = Self::contract(contract_id);
+            contract.execution_block = <system::Module<T>>::block_number();
+            contract.triggered = true;
+            <Contracts<T, I>>::insert(contract_id, &contract);
+
+            T::OnSwitchFired::on_switch_fired(contract_id, &contract.trustor, &contract.beneficiary);
+
+            Self::deposit_event(RawEvent::ContractForceExecuted(contract_id));
+
+            Ok(())
+        }
+
+        /// This call allows root (e.g. following a court order) to assign a new beneficiary to a contract.
+        pub fn force_set_beneficiary(origin, contract_id: ContractId, beneficiary: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let contract = Self::contract(contract_id);
+            let prev_beneficiary = contract.beneficiary.clone();
+
+            Self::do_update_beneficiary(contract_id, contract, beneficiary.clone())?;
+
+            Self::deposit_event(RawEvent::BeneficiaryForceSet(contract_id, prev_beneficiary, beneficiary));
+
+            Ok(())
+        }
+
+        /// This call allows root to add an account to the pool of jurors which vote on disputes.
+        pub fn add_juror(origin, juror: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(!Self::is_juror(&juror), "This account is already a juror");
+
+            <Jurors<T, I>>::insert(&juror, true);
+
+            Self::deposit_event(RawEvent::JurorAdded(juror));
+
+            Ok(())
+        }
+
+        /// This call allows root to remove an account from the pool of jurors. Votes which were
+        /// already cast are kept.
+        pub fn remove_juror(origin, juror: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(Self::is_juror(&juror), "This account is not a juror");
+
+            <Jurors<T, I>>::remove(&juror);
+
+            Self::deposit_event(RawEvent::JurorRemoved(juror));
+
+            Ok(())
+        }
+
+        /// This call allows root to set the bond and voting period of new disputes.
+        pub fn set_dispute_parameters(origin, bond: BalanceOf<T, I>, voting_period: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <DisputeBond<T, I>>::put(bond);
+            <DisputeVotingPeriod<T, I>>::put(voting_period);
+
+            Ok(())
+        }
+
+        /// This call allows the trustor or beneficiary of a contract to dispute it by reserving a
+        /// bond. The beneficiary cannot act as the trustor until the dispute is resolved.
+        ///
+        /// The bond is refunded if the verdict favors the challenger, and is otherwise given to the
+        /// other party.
+        pub fn open_dispute(origin, contract_id: ContractId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let contract = Self::contract(contract_id);
+            ensure!(
+                sender == contract.trustor || sender == contract.beneficiary,
+                "You are not a party to this contract"
+            );
+            Self::ensure_undisputed(contract_id)?;
+
+            let bond = Self::dispute_bond();
+            T::Currency::reserve(&sender, bond)?;
+
+            let dispute = Dispute {
+                challenger: sender.clone(),
+                bond,
+                deadline: <system::Module<T>>::block_number() + Self::dispute_voting_period(),
+                tally: Vec::new(),
+                voters: Vec::new(),
+            };
+            <Disputes<T, I>>::insert(contract_id, dispute);
+
+            Self::deposit_event(RawEvent::DisputeOpened(contract_id, sender));
+
+            Ok(())
+        }
+
+        /// This call allows a juror to vote for the verdict of an open dispute.
+        pub fn vote_dispute(origin, contract_id: ContractId, verdict: Verdict<T::AccountId>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_juror(&sender), "You are not a juror");
+
+            let mut dispute = Self::dispute(contract_id).ok_or("This contract is not under dispute")?;
+            ensure!(<system::Module<T>>::block_number() <= dispute.deadline, "The voting period of this dispute is over");
+            ensure!(Self::dispute_vote((contract_id, sender.clone())).is_none(), "You have already voted on this dispute");
+
+            Self::ensure_applicable_verdict(&Self::contract(contract_id), &verdict)?;
+
+            match dispute.tally.iter_mut().find(|(tallied, _)| tallied == &verdict) {
+                Some((_, votes)) => *votes += 1,
+                None => dispute.tally.push((verdict.clone(), 1)),
+            }
+            dispute.voters.push(sender.clone());
+            <Disputes<T, I>>::insert(contract_id, dispute);
+            <DisputeVotes<T, I>>::insert((contract_id, sender.clone()), verdict);
+
+            Self::deposit_event(RawEvent::DisputeVoted(contract_id, sender));
+
+            Ok(())
+        }
+
+        /// This call allows anyone to resolve a dispute once its voting period is over.
+        ///
+        /// The verdict with the most votes is applied. Without votes, or when the vote is tied,
+        /// the contract is kept as it is and the bond is refunded.
+        pub fn resolve_dispute(origin, contract_id: ContractId) -> Result {
+            ensure_signed(origin)?;
+
+            let dispute = Self::dispute(contract_id).ok_or("This contract is not under dispute")?;
+            ensure!(dispute.deadline < <system::Module<T>>::block_number(), "The dispute is still open for voting");
+
+            let contract = Self::contract(contract_id);
+            let verdict = Self::winning_verdict(&dispute.tally);
+            if let Some(ref verdict) = verdict {
+                Self::ensure_applicable_verdict(&contract, verdict)?;
+            }
+
+            let challenger_favored = match verdict {
+                None => true,
+                Some(Verdict::UpholdTakeover) => dispute.challenger == contract.beneficiary,
+                Some(_) => dispute.challenger == contract.trustor,
+            };
+
+            // Settle the bond before any other write, since it is the only step which can still fail
+            if challenger_favored {
+                T::Currency::unreserve(&dispute.challenger, dispute.bond);
+            } else {
+                let other_party = if dispute.challenger == contract.trustor {
+                    &contract.beneficiary
+                } else {
+                    &contract.trustor
+                };
+                T::Currency::repatriate_reserved(&dispute.challenger, other_party, dispute.bond)?;
+            }
+            Self::remove_dispute(contract_id);
+
+            match verdict {
+                Some(Verdict::CancelContract) => Self::do_delete_contract(contract_id, &contract)?,
+                Some(Verdict::ReassignBeneficiary(ref beneficiary)) => {
+                    Self::do_update_beneficiary(contract_id, contract.clone(), beneficiary.clone())?
+                }
+                Some(Verdict::UpholdTakeover) | None => {}
+            }
+
+            Self::deposit_event(RawEvent::DisputeResolved(contract_id, verdict));
+
+            Ok(())
+        }
+
+        /// This call allows a user to name the friends who can recover their account if they lose
+        /// their key, and how many of them must agree.
+        pub fn set_recovery_config(origin, friends: Vec<T::AccountId>, threshold: u32, delay: T::BlockNumber) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(!friends.is_empty(), "You must name at least one friend");
+            ensure!(friends.len() <= MAX_RECOVERY_FRIENDS, "You named too many friends");
+            ensure!(!friends.contains(&sender), "You cannot name yourself as a friend");
+            ensure!(threshold > 0 && threshold as usize <= friends.len(), "Your recovery threshold is invalid");
+            ensure!(!<ActiveRecoveries<T, I>>::exists(&sender), "Your account is being recovered");
+
+            let mut sorted_friends = friends.clone();
+            sorted_friends.sort();
+            sorted_friends.dedup();
+            ensure!(sorted_friends.len() == friends.len(), "You named a friend more than once");
+
+            <RecoveryConfigs<T, I>>::insert(&sender, RecoveryConfig { friends, threshold, delay });
+
+            Self::deposit_event(RawEvent::RecoveryConfigured(sender));
+
+            Ok(())
+        }
+
+        /// This call allows a friend of an account to start recovering it to a new controller key.
+        /// The initiating friend approves the recovery.
+        pub fn initiate_recovery(origin, account: T::AccountId, new_controller: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let config = Self::recovery_config(&account).ok_or("This account cannot be recovered")?;
+            ensure!(config.friends.contains(&sender), "You are not a friend of this account");
+            ensure!(!<ActiveRecoveries<T, I>>::exists(&account), "This account is already being recovered");
+            ensure!(new_controller != account, "You cannot recover an account to itself");
+
+            let recovery = ActiveRecovery {
+                new_controller: new_controller.clone(),
+                started: <system::Module<T>>::block_number(),
+                approvals: [sender.clone()].to_vec(),
+            };
+            <ActiveRecoveries<T, I>>::insert(&account, recovery);
+
+            Self::deposit_event(RawEvent::RecoveryInitiated(account.clone(), new_controller));
+            Self::deposit_event(RawEvent::RecoveryApproved(account, sender));
+
+            Ok(())
+        }
+
+        /// This call allows a friend of an account to approve its recovery.
+        pub fn approve_recovery(origin, account: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let config = Self::recovery_config(&account).ok_or("This account cannot be recovered")?;
+            ensure!(config.friends.contains(&sender), "You are not a friend of this account");
+
+            let mut recovery = Self::active_recovery(&account).ok_or("This account is not being recovered")?;
+            ensure!(!recovery.approvals.contains(&sender), "You have already approved this recovery");
+
+            recovery.approvals.push(sender.clone());
+            <ActiveRecoveries<T, I>>::insert(&account, recovery);
+
+            Self::deposit_event(RawEvent::RecoveryApproved(account, sender));
+
+            Ok(())
+        }
+
+        /// This call allows a user to cancel a recovery of their account with their current key.
+        pub fn cancel_recovery(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<ActiveRecoveries<T, I>>::exists(&sender), "Your account is not being recovered");
+
+            <ActiveRecoveries<T, I>>::remove(&sender);
+
+            Self::deposit_event(RawEvent::RecoveryCancelled(sender));
+
+            Ok(())
+        }
+
+        /// This call allows anyone to complete the recovery of an account once enough friends
+        /// approved it and the account did not cancel it during the recovery delay.
+        ///
+        /// The new controller becomes a ping delegate of the account, so that its contracts do not
+        /// fire on a user who is still alive.
+        pub fn complete_recovery(origin, account: T::AccountId) -> Result {
+            ensure_signed(origin)?;
+
+            let config = Self::recovery_config(&account).ok_or("This account cannot be recovered")?;
+            let recovery = Self::active_recovery(&account).ok_or("This account is not being recovered")?;
+            ensure!(
+                recovery.approvals.len() >= config.threshold as usize,
+                "This recovery does not have enough approvals"
+            );
+            ensure!(
+                recovery.started + config.delay < <system::Module<T>>::block_number(),
+                "The delay of this recovery is not over"
+            );
+
+            <ActiveRecoveries<T, I>>::remove(&account);
+            if let Some(prev_controller) = Self::recovery_controller(&account) {
+                Self::do_remove_ping_delegate(account.clone(), prev_controller);
+            }
+            <RecoveryControllers<T, I>>::insert(&account, &recovery.new_controller);
+            if !Self::is_ping_delegate((account.clone(), recovery.new_controller.clone())) {
+                Self::do_add_ping_delegate(account.clone(), recovery.new_controller.clone());
+            }
+
+            Self::deposit_event(RawEvent::AccountRecovered(account, recovery.new_controller));
+
+            Ok(())
+        }
+
+        /// This call allows a recovered account, or its controller, to end the control of the
+        /// controller over the account. The controller is no longer a ping delegate either.
+        pub fn revoke_recovery(origin, account: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let controller = Self::recovery_controller(&account).ok_or("This account was not recovered")?;
+            ensure!(
+                sender == account || sender == controller,
+                "You are not the account or the controller of this recovery"
+            );
+
+            Self::do_revoke_recovery(account, controller);
+
+            Ok(())
+        }
+
+        /// This call allows root to end the control of the controller over a recovered account.
+        pub fn force_revoke_recovery(origin, account: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            let controller = Self::recovery_controller(&account).ok_or("This account was not recovered")?;
+
+            Self::do_revoke_recovery(account, controller);
+
+            Ok(())
+        }
+
+        /// This call allows the controller of a recovered account to make calls on its behalf.
+        pub fn act_as_recovered(origin, account: T::AccountId, call: T::TrustorCall) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(
+                Self::recovery_controller(&account) == Some(sender.clone()),
+                "You are not the controller of this account"
+            );
+
+            call.dispatch(RawOrigin::Signed(account.clone()).into())?;
+
+            Self::deposit_event(RawEvent::ActedAsRecovered(account, sender));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to prolong the `execution_block` time of all of their contracts.
+        ///
+        /// The transaction fee is refunded for the first `FeeExemptPings` pings of each period.
+        pub fn ping_alive(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::duress_commitment(&sender).is_none(), "Your pings must include a token");
+
+            Self::do_ping_alive(sender.clone())?;
+            Self::refund_ping_fee(&sender);
+
+            Ok(())
+        }
+
+        /// This call is the same as `ping_alive`, but includes a token which can secretly signal
+        /// that the trustor is under duress.
+        ///
+        /// A normal ping uses a random token, while a duress ping uses `hash(secret, index)`, where
+        /// `index` is `ping_tokens_count` for the trustor. Both look the same on chain until the
+        /// beneficiary reveals the secret through `claim_duress`.
+        pub fn ping_alive_with_token(origin, token: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_ping_alive(sender.clone())?;
+            Self::record_ping_token(&sender, token);
+            Self::refund_ping_fee(&sender);
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to commit to the hash of a duress secret, which they
+        /// share off-chain with their beneficiaries. Once set, every ping must include a token.
+        ///
+        /// No event is deposited, so that setting up duress pings draws as little attention as possible.
+        pub fn set_duress_commitment(origin, commitment: Option<T::Hash>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            match commitment {
+                Some(commitment) => <DuressCommitments<T, I>>::insert(&sender, commitment),
+                None => <DuressCommitments<T, I>>::remove(&sender),
+            }
+
+            Ok(())
+        }
+
+        /// This call allows a user ("beneficiary") to reveal the duress secret of their trustor
+        /// and prove that one of the trustor's recent pings was made under duress, which makes
+        /// the contract executable right away.
+        ///
+        /// The contract is marked as triggered, so it cannot be pinged back by whoever holds the
+        /// trustor's key.
+        pub fn claim_duress(origin, contract_id: ContractId, secret: T::Hash, ping_index: u64) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+            let mut contract = Self::contract(contract_id);
+            ensure!(contract.beneficiary == sender, "You are not the beneficiary for this trustor");
+
+            let commitment = Self::duress_commitment(&contract.trustor)
+                .ok_or("Your trustor does not have a duress commitment")?;
+            ensure!(T::Hashing::hash_of(&secret) == commitment, "Your duress secret is invalid");
+
+            let token = Self::ping_token((contract.trustor.clone(), ping_index));
+            ensure!(
+                token == Some(T::Hashing::hash_of(&(secret, ping_index))),
+                "This ping was not made under duress"
+            );
+
+            let current_block = <system::Module<T>>::block_number();
+            if current_block < contract.execution_block {
+                contract.execution_block = current_block;
+            }
+            contract.triggered = true;
+            <Contracts<T, I>>::insert(contract_id, &contract);
+
+            T::OnSwitchFired::on_switch_fired(contract_id, &contract.trustor, &sender);
+
+            Self::deposit_event(RawEvent::DuressClaimed(contract_id));
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to authorize another account ("delegate") to ping
+        /// alive on their behalf, so that the trustor's own key can be kept offline.
+        ///
+        /// A delegate has no authority over the trustor's account other than `ping_alive_for`.
+        pub fn add_ping_delegate(origin, delegate: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(sender != delegate, "You cannot use yourself as your ping delegate");
+            ensure!(!Self::is_ping_delegate((sender.clone(), delegate.clone())), "This account is already your ping delegate");
+
+            Self::do_add_ping_delegate(sender, delegate);
+
+            Ok(())
+        }
+
+        /// This call allows a user ("trustor") to revoke a previously authorized ping delegate.
+        pub fn remove_ping_delegate(origin, delegate: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_ping_delegate((sender.clone(), delegate.clone())), "This account is not your ping delegate");
+
+            Self::do_remove_ping_delegate(sender, delegate);
+
+            Ok(())
+        }
+
+        /// This call allows a ping delegate to prolong the `execution_block` time of their trustor.
+        pub fn ping_alive_for(origin, trustor: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_ping_delegate((trustor.clone(), sender.clone())), "You are not a ping delegate for this trustor");
+
+            Self::do_ping_alive(trustor)?;
+            Self::refund_ping_fee(&sender);
+
+            Ok(())
+        }
+
+        /// This call allows root to trust an account (e.g. a notary) to attest to the death of trustors.
+        pub fn add_attester(origin, attester: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(!Self::is_attester(&attester), "This account is already an attester");
+
+            <Attesters<T, I>>::insert(&attester, true);
+
+            Self::deposit_event(RawEvent::AttesterAdded(attester));
+
+            Ok(())
+        }
+
+        /// This call allows root to stop trusting an attester. Attestations which were already
+        /// made are kept.
+        pub fn remove_attester(origin, attester: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(Self::is_attester(&attester), "This account is not an attester");
+
+            <Attesters<T, I>>::remove(&attester);
+
+            Self::deposit_event(RawEvent::AttesterRemoved(attester));
+
+            Ok(())
+        }
+
+        /// This call allows root to configure how many attestations are needed and how soon a
+        /// trustor's contracts become executable once they are reached.
+        pub fn set_attestation_quorum(origin, quorum: u32, block_delay: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(quorum > 0, "The attestation quorum must not be zero");
+
+            <AttestationQuorum<T, I>>::put(quorum);
+            <AttestedBlockDelay<T, I>>::put(block_delay);
+
+            Ok(())
+        }
+
+        /// This call allows an attester to attest to the death of a trustor.
+        ///
+        /// Once a quorum of attesters agrees, every contract of the trustor becomes executable
+        /// within `AttestedBlockDelay` blocks, unless the trustor voids the attestations by pinging alive.
+        pub fn attest_death(origin, trustor: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_attester(&sender), "You are not an attester");
+            ensure!(Self::contracts_count(&trustor) > 0, "You selected a trustor without a contract");
+
+            let mut attestations = Self::attestations(&trustor);
+            ensure!(!attestations.contains(&sender), "You have already attested to this trustor's death");
+
+            attestations.push(sender.clone());
+            // The quorum may have been lowered since, so it is reached by any number of attestations
+            // at or above it, but the contracts are only shortened once
+            let quorum_reached = !Self::quorum_reached(&trustor)
+                && attestations.len() >= Self::attestation_quorum() as usize;
+            <Attestations<T, I>>::insert(&trustor, attestations);
+
+            Self::deposit_event(RawEvent::DeathAttested(trustor.clone(), sender));
+
+            if quorum_reached {
+                <QuorumReached<T, I>>::insert(&trustor, true);
+                Self::shorten_contracts(&trustor);
+                Self::deposit_event(RawEvent::AttestationQuorumReached(trustor));
+            }
+
+            Ok(())
+        }
+
+        /// This call allows an attester to revoke their attestation of a trustor's death.
+        pub fn revoke_attestation(origin, trustor: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut attestations = Self::attestations(&trustor);
+            let position = attestations.iter().position(|attester| attester == &sender)
+                .ok_or("You have not attested to this trustor's death")?;
+
+            attestations.swap_remove(position);
+            if attestations.len() < Self::attestation_quorum() as usize {
+                <QuorumReached<T, I>>::remove(&trustor);
+            }
+            <Attestations<T, I>>::insert(&trustor, attestations);
+
+            Self::deposit_event(RawEvent::AttestationRevoked(trustor, sender));
+
+            Ok(())
+        }
+
+        /// This unsigned call allows anyone to relay a liveness proof which was signed off-chain
+        /// by a trustor, so that a trustor without funds for fees can still ping alive.
+        ///
+        /// The proof is a signature by the trustor over `(trustor, nonce, genesis_hash)` and each
+        /// nonce may only be used once.
+        pub fn submit_liveness_proof(origin, trustor: T::AccountId, nonce: u64, signature: T::Signature) -> Result {
+            ensure_inherent(origin)?;
+
+            ensure!(nonce == Self::liveness_proof_nonce(&trustor), "Your liveness proof nonce is invalid");
+            ensure!(Self::verify_liveness_proof(&trustor, nonce, &signature), "Your liveness proof signature is invalid");
+
+            let new_nonce = nonce.checked_add(1)
+                .ok_or("Overflow incrementing the liveness proof nonce")?;
+            <LivenessProofNonce<T, I>>::insert(&trustor, new_nonce);
+
+            Self::do_ping_alive(trustor)
+        }
+    }
+}
+
+impl<T: Trait<I>, I: Instance> Module<T, I> {
+    /// Validate an unsigned call before it is admitted to the transaction pool.
+    ///
+    /// Only `submit_liveness_proof` may be submitted unsigned, and only when it carries a valid
+    /// signature for the trustor's current nonce.
+    pub fn validate_unsigned(call: &Call<T, I>) -> TransactionValidity {
+        match call {
+            Call::submit_liveness_proof(trustor, nonce, signature) => {
+                if Self::contracts_count(trustor) == 0 {
+                    return TransactionValidity::Invalid(ApplyError::BadSignature as i8);
+                }
+                if *nonce != Self::liveness_proof_nonce(trustor) {
+                    return TransactionValidity::Invalid(ApplyError::Stale as i8);
+                }
+                if !Self::verify_liveness_proof(trustor, *nonce, signature) {
+                    return TransactionValidity::Invalid(ApplyError::BadSignature as i8);
+                }
+
+                TransactionValidity::Valid {
+                    priority: LIVENESS_PRIORITY,
+                    requires: Vec::new(),
+                    provides: [(I::PREFIX, trustor, nonce).encode()].to_vec(),
+                    longevity: LIVENESS_LONGEVITY,
+                }
+            }
+            _ => TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+        }
+    }
+
+    /// The recent calls which beneficiaries made on behalf of a trustor, oldest first.
+    pub fn audit_log(trustor: &T::AccountId) -> Vec<AuditEntryOf<T>> {
+        let count = Self::audit_log_count(trustor);
+        (count.saturating_sub(AUDIT_LOG_LENGTH)..count)
+            .filter_map(|index| Self::audit_entry((trustor.clone(), index)))
+            .collect()
+    }
+
+    /// Whether a signed call proves that a trustor is alive, and its sender can actually ping
+    /// for a trustor who has a contract.
+    ///
+    /// Unsigned liveness proofs are checked by `validate_unsigned` instead.
+    pub fn is_liveness_call(sender: &T::AccountId, call: &Call<T, I>) -> bool {
+        match call {
+            Call::ping_alive() | Call::ping_alive_with_token(..) => Self::contracts_count(sender) > 0,
+            Call::ping_alive_for(trustor) => {
+                Self::is_ping_delegate((trustor.clone(), sender.clone())) && Self::contracts_count(trustor) > 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Fetch a contract, making sure that it belongs to the given trustor.
+    fn trustor_contract(
+        trustor: &T::AccountId,
+        contract_id: ContractId,
+    ) -> rstd::result::Result<ContractOf<T, I>, &'static str> {
+        ensure!(<Contracts<T, I>>::exists(contract_id), "This contract does not exist");
+
+        let contract = Self::contract(contract_id);
+        ensure!(&contract.trustor == trustor, "You are not the trustor for this contract");
+
+        let current_block = <system::Module<T>>::block_number();
+        ensure!(
+            !contract.triggered || current_block < contract.execution_block,
+            "Your contract was triggered and can no longer be changed"
+        );
+
+        Ok(contract)
+    }
+
+    /// Allow an account to ping alive on behalf of a trustor.
+    fn do_add_ping_delegate(trustor: T::AccountId, delegate: T::AccountId) {
+        <PingDelegates<T, I>>::insert((trustor.clone(), delegate.clone()), true);
+        <PingDelegatesCount<T, I>>::mutate(&trustor, |count| *count = count.saturating_add(1));
+
+        Self::deposit_event(RawEvent::PingDelegateAdded(trustor, delegate));
+    }
+
+    /// Stop an account from pinging alive on behalf of a trustor, if it is their ping delegate.
+    fn do_remove_ping_delegate(trustor: T::AccountId, delegate: T::AccountId) {
+        if !Self::is_ping_delegate((trustor.clone(), delegate.clone())) {
+            return;
+        }
+
+        <PingDelegates<T, I>>::remove((trustor.clone(), delegate.clone()));
+        <PingDelegatesCount<T, I>>::mutate(&trustor, |count| *count = count.saturating_sub(1));
+
+        Self::deposit_event(RawEvent::PingDelegateRemoved(trustor, delegate));
+    }
+
+    /// Remove the controller of a recovered account, along with its ping delegation.
+    fn do_revoke_recovery(account: T::AccountId, controller: T::AccountId) {
+        <RecoveryControllers<T, I>>::remove(&account);
+        Self::do_remove_ping_delegate(account.clone(), controller.clone());
+
+        Self::deposit_event(RawEvent::RecoveryRevoked(account, controller));
+    }
+
+    /// Remove a contract along with its list entries, escrow and vesting schedule.
+    fn do_delete_contract(contract_id: ContractId, contract: &ContractOf<T, I>) -> Result {
+        // The beneficiary's list entry is already gone if their account was reaped
+        if <TrustorsIndex<T, I>>::exists(contract_id) {
+            Self::remove_trustor(&contract.beneficiary, contract_id)?;
+        }
+        Self::remove_contract(&contract.trustor, contract_id)?;
+
+        // Return any escrow to the trustor
+        let escrow = <Escrows<T, I>>::take(contract_id);
+        T::Currency::unreserve(&contract.trustor, escrow);
+
+        // Refund the bond of a dispute which can no longer be resolved
+        if let Some(dispute) = Self::remove_dispute(contract_id) {
+            T::Currency::unreserve(&dispute.challenger, dispute.bond);
+        }
+        Self::end_takeover(contract_id, None)?;
+
+        <Contracts<T, I>>::remove(contract_id);
+        <VestingSchedules<T, I>>::remove(contract_id);
+        <FailedClaims<T, I>>::remove(contract_id);
+
+        T::OnContractChanged::on_contract_deleted(contract_id, &contract.trustor);
+
+        Ok(())
+    }
+
+    /// Move a contract from its current beneficiary's list of trustors to a new beneficiary's.
+    fn do_update_beneficiary(contract_id: ContractId, mut contract: ContractOf<T, I>, beneficiary: T::AccountId) -> Result {
+        ensure!(contract.trustor != beneficiary, "You cannot use yourself as your beneficiary");
+        ensure!(contract.beneficiary != beneficiary, "Your beneficiary is already set to this account");
+
+        if <TrustorsIndex<T, I>>::exists(contract_id) {
+            Self::remove_trustor(&contract.beneficiary, contract_id)?;
+        }
+        Self::add_trustor(&beneficiary, contract_id)?;
+        Self::end_takeover(contract_id, None)?;
+
+        T::OnContractChanged::on_beneficiary_changed(contract_id, &contract.trustor, &contract.beneficiary, &beneficiary);
+
+        contract.beneficiary = beneficiary;
+        <Contracts<T, I>>::insert(contract_id, &contract);
+        <FailedClaims<T, I>>::remove(contract_id);
+
+        Ok(())
+    }
+
+    /// The takeover of a contract, once its grace period is over, or `None` if the trustor
+    /// triggered the contract and no takeover is needed.
+    fn finished_takeover(
+        contract_id: ContractId,
+        contract: &ContractOf<T, I>,
+    ) -> rstd::result::Result<Option<TakeoverOf<T, I>>, &'static str> {
+        if contract.triggered {
+            return Ok(None);
+        }
+
+        let takeover = Self::takeover(contract_id).ok_or("You have not initiated a takeover of this contract")?;
+        ensure!(
+            takeover.grace_end < <system::Module<T>>::block_number(),
+            "The grace period of this takeover is not over"
+        );
+
+        Ok(Some(takeover))
+    }
+
+    /// Refund the bond of a finished takeover to its initiator, if it was not refunded yet.
+    fn refund_takeover_bond(contract_id: ContractId, takeover: Option<TakeoverOf<T, I>>) {
+        if let Some(mut takeover) = takeover {
+            if !takeover.bond.is_zero() {
+                let bond = takeover.bond;
+                T::Currency::unreserve(&takeover.initiator, bond);
+                takeover.bond = Zero::zero();
+                <Takeovers<T, I>>::insert(contract_id, takeover);
+                Self::deposit_event(RawEvent::TakeoverBondRefunded(contract_id, bond));
+            }
+        }
+    }
+
+    /// Remove the takeover of a contract, if there is one. Its bond is given to `slash_to` when
+    /// set, and is otherwise refunded to the initiator.
+    fn end_takeover(contract_id: ContractId, slash_to: Option<&T::AccountId>) -> Result {
+        let takeover = match <Takeovers<T, I>>::take(contract_id) {
+            Some(takeover) => takeover,
+            None => return Ok(()),
+        };
+        if takeover.bond.is_zero() {
+            return Ok(());
+        }
+
+        match slash_to {
+            Some(trustor) => {
+                T::Currency::repatriate_reserved(&takeover.initiator, trustor, takeover.bond)?;
+                Self::deposit_event(RawEvent::TakeoverBondSlashed(contract_id, takeover.bond));
+            }
+            None => {
+                T::Currency::unreserve(&takeover.initiator, takeover.bond);
+                Self::deposit_event(RawEvent::TakeoverBondRefunded(contract_id, takeover.bond));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append a call to the audit log of a trustor, dropping the oldest entry once the log is full.
+    fn record_audit_entry(
+        trustor: &T::AccountId,
+        contract_id: ContractId,
+        beneficiary: T::AccountId,
+        call_hash: T::Hash,
+        succeeded: bool,
+    ) {
+        let entry = AuditEntry {
+            block_number: <system::Module<T>>::block_number(),
+            contract_id,
+            beneficiary,
+            call_hash,
+            succeeded,
+        };
+
+        let entry_index = Self::audit_log_count(trustor);
+        <AuditLog<T, I>>::insert((trustor.clone(), entry_index), entry);
+        <AuditLogCount<T, I>>::insert(trustor, entry_index.saturating_add(1));
+
+        if let Some(expired_index) = entry_index.checked_sub(AUDIT_LOG_LENGTH) {
+            <AuditLog<T, I>>::remove((trustor.clone(), expired_index));
+        }
+    }
+
+    /// Check that an account is the beneficiary of a contract, or one of their heirs if the
+    /// contract is transitive.
+    fn ensure_heir(contract: &ContractOf<T, I>, heir: &T::AccountId) -> Result {
+        if &contract.beneficiary == heir {
+            return Ok(());
+        }
+
+        let mut visited = [contract.trustor.clone()].to_vec();
+        ensure!(
+            contract.transitive && Self::is_heir(&contract.beneficiary, heir, MAX_INHERITANCE_DEPTH - 1, &mut visited),
+            "You are not the beneficiary for this trustor"
+        );
+
+        Ok(())
+    }
+
+    /// Whether `heir` can inherit from `trustor` through a chain of at most `depth` executable
+    /// contracts, each of which except the last is transitive.
+    ///
+    /// Trustors which were already visited are skipped, so that cycles of beneficiaries end.
+    fn is_heir(trustor: &T::AccountId, heir: &T::AccountId, depth: u32, visited: &mut Vec<T::AccountId>) -> bool {
+        if depth == 0 || visited.contains(trustor) {
+            return false;
+        }
+        visited.push(trustor.clone());
+
+        let current_block = <system::Module<T>>::block_number();
+        for index in 0..Self::contracts_count(trustor) {
+            let contract_id = Self::contracts_by_index((trustor.clone(), index));
+            let contract = Self::contract(contract_id);
+            if current_block < contract.execution_block
+                || contract.handover != Handover::Takeover
+                || <Disputes<T, I>>::exists(contract_id)
+            {
+                continue;
+            }
+
+            if &contract.beneficiary == heir {
+                return true;
+            }
+            if contract.transitive && Self::is_heir(&contract.beneficiary, heir, depth - 1, visited) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Check that a contract is not under dispute.
+    fn ensure_undisputed(contract_id: ContractId) -> Result {
+        ensure!(!<Disputes<T, I>>::exists(contract_id), "This contract is under dispute");
+        Ok(())
+    }
+
+    /// Check that a contract has no claim secret left to reveal.
+    fn ensure_secret_claimed(contract: &ContractOf<T, I>) -> Result {
+        ensure!(
+            contract.claim_commitment.is_none(),
+            "You have not revealed the claim secret of this contract"
+        );
+        Ok(())
+    }
+
+    /// Remove the dispute over a contract along with its votes.
+    fn remove_dispute(contract_id: ContractId) -> Option<DisputeOf<T, I>> {
+        let dispute = <Disputes<T, I>>::take(contract_id)?;
+        for voter in &dispute.voters {
+            <DisputeVotes<T, I>>::remove((contract_id, voter.clone()));
+        }
+        Some(dispute)
+    }
+
+    /// Check that a verdict can still be applied to a contract.
+    fn ensure_applicable_verdict(contract: &ContractOf<T, I>, verdict: &Verdict<T::AccountId>) -> Result {
+        if let Verdict::ReassignBeneficiary(ref beneficiary) = verdict {
+            ensure!(beneficiary != &contract.trustor, "You cannot reassign a contract to its trustor");
+            ensure!(beneficiary != &contract.beneficiary, "You cannot reassign a contract to its beneficiary");
+        }
+        Ok(())
+    }
+
+    /// The verdict with the most votes, if there is a single one.
+    fn winning_verdict(tally: &[(Verdict<T::AccountId>, u32)]) -> Option<Verdict<T::AccountId>> {
+        let mut winner: Option<&(Verdict<T::AccountId>, u32)> = None;
+        let mut tied = false;
+        for entry in tally {
+            match winner {
+                Some(leader) if entry.1 < leader.1 => {}
+                Some(leader) if entry.1 == leader.1 => tied = true,
+                _ => {
+                    winner = Some(entry);
+                    tied = false;
+                }
+            }
+        }
+
+        if tied {
+            None
+        } else {
+            winner.map(|(verdict, _)| verdict.clone())
+        }
+    }
+
+    /// Check that a call is allowed by the scope of a contract.
+    fn ensure_in_scope(scope: &CallScope<BalanceOf<T, I>>, call: &T::TrustorCall) -> Result {
+        match scope {
+            CallScope::All => Ok(()),
+            CallScope::TransfersUpTo(max_value) => match call.transfer_value() {
+                Some(ref value) if value <= max_value => Ok(()),
+                _ => Err("This call is outside of the contract's scope"),
+            },
+        }
+    }
+
+    /// Check that a call transfers to one of a contract's allowed destinations, if it has any.
+    fn ensure_allowed_destination(allowed_destinations: &[T::AccountId], call: &T::TrustorCall) -> Result {
+        if allowed_destinations.is_empty() {
+            return Ok(());
+        }
+
+        match call.transfer_dest() {
+            Some(ref dest) if allowed_destinations.contains(dest) => Ok(()),
+            _ => Err("This call does not transfer to an allowed destination"),
+        }
+    }
+
+    /// Check that a payout goes to one of a contract's allowed destinations, if it has any.
+    fn ensure_allowed_payout(allowed_destinations: &[T::AccountId], dest: &T::AccountId) -> Result {
+        ensure!(
+            allowed_destinations.is_empty() || allowed_destinations.contains(dest),
+            "This payout does not go to an allowed destination"
+        );
+        Ok(())
+    }
+
+    /// The part of a vesting schedule's total balance which is released by the given block.
+    fn vested_balance(
+        schedule: &VestingSchedule<BalanceOf<T, I>, T::BlockNumber>,
+        now: T::BlockNumber,
+    ) -> rstd::result::Result<BalanceOf<T, I>, &'static str> {
+        let elapsed = now.saturating_sub(schedule.starting_block);
+        if elapsed >= schedule.period {
+            return Ok(schedule.total);
+        }
+
+        let elapsed = BalanceOf::<T, I>::sa(elapsed.as_());
+        let period = BalanceOf::<T, I>::sa(schedule.period.as_());
+        schedule.total.checked_mul(&elapsed)
+            .map(|value| value / period)
+            .ok_or("Overflow calculating the vested balance")
+    }
+
+    /// Append a contract to a trustor's list of contracts.
+    fn add_contract(trustor: &T::AccountId, contract_id: ContractId) -> Result {
+        let contracts_count = Self::contracts_count(trustor);
+        let new_contracts_count = contracts_count.checked_add(1)
+            .ok_or("Overflow adding a new contract for this trustor")?;
+
+        <ContractsArray<T, I>>::insert((trustor.clone(), contracts_count), contract_id);
+        <ContractsCount<T, I>>::insert(trustor, new_contracts_count);
+        <ContractsIndex<T, I>>::insert(contract_id, contracts_count);
+
+        Ok(())
+    }
+
+    /// Remove a contract from a trustor's list of contracts.
+    fn remove_contract(trustor: &T::AccountId, contract_id: ContractId) -> Result {
+        ensure!(<ContractsIndex<T, I>>::exists(contract_id), "Your account is in a bad state");
+
+        let contracts_count = Self::contracts_count(trustor);
+        let new_contracts_count = contracts_count.checked_sub(1)
+            .ok_or("Underflow removing a contract for this trustor")?;
+
+        // Prepare to remove the last contract from the trustor's list
+        let mut contract_index = Self::contract_index(contract_id);
+        if contract_index != new_contracts_count {
+            let last_contract_id = Self::contracts_by_index((trustor.clone(), new_contracts_count));
+            <ContractsArray<T, I>>::insert((trustor.clone(), contract_index), last_contract_id);
+            <ContractsIndex<T, I>>::insert(last_contract_id, contract_index);
+            contract_index = new_contracts_count;
+        }
+
+        <ContractsArray<T, I>>::remove((trustor.clone(), contract_index));
+        <ContractsCount<T, I>>::insert(trustor, new_contracts_count);
+        <ContractsIndex<T, I>>::remove(contract_id);
+
+        Ok(())
+    }
+
+    /// Append a contract to a beneficiary's list of trustor contracts.
+    fn add_trustor(beneficiary: &T::AccountId, contract_id: ContractId) -> Result {
+        let trustors_count = Self::trustors_count(beneficiary);
+        let new_trustors_count = trustors_count.checked_add(1)
+            .ok_or("Overflow adding a new trustor for this beneficiary")?;
+
+        <TrustorsArray<T, I>>::insert((beneficiary.clone(), trustors_count), contract_id);
+        <TrustorsCount<T, I>>::insert(beneficiary, new_trustors_count);
+        <TrustorsIndex<T, I>>::insert(contract_id, trustors_count);
+
+        Ok(())
+    }
+
+    /// Remove a contract from a beneficiary's list of trustor contracts.
+    fn remove_trustor(beneficiary: &T::AccountId, contract_id: ContractId) -> Result {
+        ensure!(<TrustorsIndex<T, I>>::exists(contract_id), "Your account is in a bad state");
+
+        let trustors_count = Self::trustors_count(beneficiary);
+        let new_trustors_count = trustors_count.checked_sub(1)
+            .ok_or("Underflow removing a trustor for this beneficiary")?;
+
+        // Prepare to remove the last trustor from the beneficiary's list
+        let mut trustor_index = Self::trustor_index(contract_id);
+        if trustor_index != new_trustors_count {
+            let last_contract_id = Self::trustors_by_index((beneficiary.clone(), new_trustors_count));
+            <TrustorsArray<T, I>>::insert((beneficiary.clone(), trustor_index), last_contract_id);
+            <TrustorsIndex<T, I>>::insert(last_contract_id, trustor_index);
+            trustor_index = new_trustors_count;
+        }
+
+        <TrustorsArray<T, I>>::remove((beneficiary.clone(), trustor_index));
+        <TrustorsCount<T, I>>::insert(beneficiary, new_trustors_count);
+        <TrustorsIndex<T, I>>::remove(contract_id);
+
+        Ok(())
+    }
+
+    /// Refund the base transaction fee of a ping, unless the account used up its quota of fee
+    /// exempt pings for the current period.
+    fn refund_ping_fee(who: &T::AccountId) {
+        let period = <FeeExemptPeriod<T, I>>::get();
+        if period.is_zero() {
+            return;
+        }
+
+        let current_period = <system::Module<T>>::block_number() / period;
+        let (last_period, used) = Self::fee_exempt_pings_used(who);
+        let used = if last_period == current_period { used } else { 0 };
+        if used >= <FeeExemptPings<T, I>>::get() {
+            return;
+        }
+
+        let fee = T::PingFee::ping_fee();
+        if T::Currency::deposit_into_existing(who, fee).is_ok() {
+            <FeeExemptPingsUsed<T, I>>::insert(who, (current_period, used + 1));
+            Self::deposit_event(RawEvent::PingFeeRefunded(who.clone(), fee));
+        }
+    }
+
+    /// Record the token of a ping for a trustor with a duress commitment, forgetting tokens which
+    /// are older than the `DURESS_PING_WINDOW`.
+    fn record_ping_token(trustor: &T::AccountId, token: T::Hash) {
+        if Self::duress_commitment(trustor).is_none() {
+            return;
+        }
+
+        let ping_index = Self::ping_tokens_count(trustor);
+        <PingTokens<T, I>>::insert((trustor.clone(), ping_index), token);
+        <PingTokensCount<T, I>>::insert(trustor, ping_index.saturating_add(1));
+
+        if let Some(expired_index) = ping_index.checked_sub(DURESS_PING_WINDOW) {
+            <PingTokens<T, I>>::remove((trustor.clone(), expired_index));
+        }
+    }
+
+    /// Check that a liveness proof was signed by the trustor for this chain and nonce.
+    fn verify_liveness_proof(trustor: &T::AccountId, nonce: u64, signature: &T::Signature) -> bool {
+        let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+        let payload = (trustor, nonce, genesis_hash).encode();
+        signature.verify(&payload[..], trustor)
+    }
+
+    /// Check that a new account signed its consent to take over a trustor's contracts on this
+    /// switch and chain, for its current migration nonce.
+    fn verify_migration(trustor: &T::AccountId, new_account: &T::AccountId, signature: &T::Signature) -> bool {
+        let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+        let nonce = Self::migration_nonce(new_account);
+        let payload = (I::PREFIX, trustor, new_account, nonce, genesis_hash).encode();
+        signature.verify(&payload[..], new_account)
+    }
+
+    /// Bring forward the `execution_block` of each of a trustor's contracts to at most
+    /// `AttestedBlockDelay` blocks from now.
+    fn shorten_contracts(trustor: &T::AccountId) {
+        let attested_block = <system::Module<T>>::block_number() + Self::attested_block_delay();
+        for index in 0..Self::contracts_count(trustor) {
+            let contract_id = Self::contracts_by_index((trustor.clone(), index));
+            let mut current_contract = Self::contract(contract_id);
+            if attested_block < current_contract.execution_block {
+                current_contract.execution_block = attested_block;
+                <Contracts<T, I>>::insert(contract_id, &current_contract);
+            }
+        }
+    }
+
+    /// Push back the `execution_block` of each of a trustor's contracts by its `block_delay`.
+    ///
+    /// A ping proves that the trustor is alive, so it applies to all of their contracts at once,
+    /// stops any vesting which has already started, ends any takeover and voids any death
+    /// attestations. The bond of a takeover which is still in its grace period is slashed.
+    fn do_ping_alive(trustor: T::AccountId) -> Result {
+        let contracts_count = Self::contracts_count(&trustor);
+        ensure!(contracts_count > 0, "You do not have a current contract");
+
+        let current_block = <system::Module<T>>::block_number();
+        for index in 0..contracts_count {
+            let contract_id = Self::contracts_by_index((trustor.clone(), index));
+            let mut current_contract = Self::contract(contract_id);
+
+            // A trigger can only be cancelled before it is confirmed
+            if current_contract.triggered {
+                if current_contract.execution_block <= current_block {
+                    continue;
+                }
+                current_contract.triggered = false;
+                Self::deposit_event(RawEvent::TriggerCancelled(contract_id));
+            }
+
+            let mut execution_block = current_block + current_contract.block_delay;
+            if current_block < current_contract.paused_until && execution_block < current_contract.execution_block {
+                execution_block = current_contract.execution_block;
+            }
+            current_contract.execution_block = execution_block.clone();
+            <Contracts<T, I>>::insert(contract_id, &current_contract);
+            <VestingSchedules<T, I>>::remove(contract_id);
+            <FailedClaims<T, I>>::remove(contract_id);
+
+            let in_grace_period = Self::takeover(contract_id)
+                .map_or(false, |takeover| current_block <= takeover.grace_end);
+            Self::end_takeover(contract_id, if in_grace_period { Some(&trustor) } else { None })?;
+
+            Self::deposit_event(RawEvent::PingedAlive(contract_id, execution_block));
+        }
+
+        if <Attestations<T, I>>::exists(&trustor) {
+            <Attestations<T, I>>::remove(&trustor);
+            <QuorumReached<T, I>>::remove(&trustor);
+            Self::deposit_event(RawEvent::AttestationsVoided(trustor));
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Trait<I>, I: Instance> OnFreeBalanceZero<T::AccountId> for Module<T, I> {
+    /// Delete the contracts of a reaped trustor, and drop a reaped beneficiary's list of trustors.
+    ///
+    /// Nothing is cleaned up while the account still holds a reserved balance, such as an escrow.
+    /// Contracts which are triggered, being vested or taken over are kept, since their beneficiary
+    /// may have drained the account themselves. The contracts of a reaped beneficiary are kept too, so that
+    /// their trustors can pick a new one.
+    fn on_free_balance_zero(who: &T::AccountId) {
+        if !T::Currency::reserved_balance(who).is_zero() {
+            return;
+        }
+
+        let contract_ids = (0..Self::contracts_count(who))
+            .map(|index| Self::contracts_by_index((who.clone(), index)))
+            .collect::<Vec<_>>();
+        for contract_id in contract_ids {
+            let contract = Self::contract(contract_id);
+            if contract.triggered
+                || <VestingSchedules<T, I>>::exists(contract_id)
+                || <Takeovers<T, I>>::exists(contract_id)
+            {
+                continue;
+            }
+            if Self::do_delete_contract(contract_id, &contract).is_err() {
+                break;
+            }
+            Self::deposit_event(RawEvent::DeletedContract(contract_id));
+        }
+        <Attestations<T, I>>::remove(who);
+        <QuorumReached<T, I>>::remove(who);
+
+        for index in 0..Self::trustors_count(who) {
+            let contract_id = Self::trustors_by_index((who.clone(), index));
+            <TrustorsArray<T, I>>::remove((who.clone(), index));
+            <TrustorsIndex<T, I>>::remove(contract_id);
+            Self::deposit_event(RawEvent::BeneficiaryReaped(contract_id, Self::contract(contract_id).trustor));
+        }
+        <TrustorsCount<T, I>>::remove(who);
+    }
+}
+
+client::decl_runtime_apis! {
+    /// The API to review what beneficiaries did on behalf of a trustor.
+    pub trait DeadMansSwitchApi<AccountId, BlockNumber, Hash> where
+        AccountId: Codec,
+        BlockNumber: Codec,
+        Hash: Codec,
+    {
+        /// The recent calls which beneficiaries made on behalf of a trustor, oldest first within
+        /// each switch.
+        ///
+        /// Each entry is tagged with the number `N` of the `InstanceN` of the switch it was made
+        /// through, since contract ids are only unique within a switch.
+        fn audit_log(trustor: AccountId) -> Vec<(u8, AuditEntry<AccountId, BlockNumber, Hash>)>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use balances::Call as BalancesCall;
+    use primitives::{Blake2Hasher, H256};
+    use runtime_io::with_externalities;
+    use std::cell::RefCell;
+    use runtime_primitives::{
+        testing::{Digest, DigestItem, Header},
+        traits::{BlakeTwo256, IdentityLookup, Lazy},
+        BuildStorage,
+    };
+    use support::{assert_err, assert_noop, assert_ok, impl_outer_origin};
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    // For testing the module, we construct most of a mock runtime. This means
+    // first constructing a configuration type (`Test`) which `impl`s each of the
+    // configuration traits of modules we want to use.
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = DMS;
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type TransferPayment = ();
+        type DustRemoval = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type Currency = balances::Module<Test>;
+        type TrustorCall = balances::Call<Test>;
+        type PingFee = balances::Module<Test>;
+        type Signature = TestSignature;
+        type OnSwitchFired = TestHooks;
+        type OnContractChanged = TestHooks;
+    }
+
+    impl Trait<Instance1> for Test {
+        type Event = ();
+        type Currency = balances::Module<Test>;
+        type TrustorCall = balances::Call<Test>;
+        type PingFee = balances::Module<Test>;
+        type Signature = TestSignature;
+        type OnSwitchFired = ();
+        type OnContractChanged = ();
+    }
+
+    thread_local! {
+        static HOOK_CALLS: RefCell<Vec<(&'static str, ContractId)>> = RefCell::new(Vec::new());
+    }
+
+    /// Mock hooks which record each call they receive.
+    pub struct TestHooks;
+
+    impl TestHooks {
+        fn record(hook: &'static str, contract_id: ContractId) {
+            HOOK_CALLS.with(|calls| calls.borrow_mut().push((hook, contract_id)));
+        }
+
+        fn calls() -> Vec<(&'static str, ContractId)> {
+            HOOK_CALLS.with(|calls| calls.borrow().clone())
+        }
+    }
+
+    impl OnSwitchFired<u64> for TestHooks {
+        fn on_switch_fired(contract_id: ContractId, _: &u64, _: &u64) {
+            Self::record("switch_fired", contract_id);
+        }
+        fn on_takeover(contract_id: ContractId, _: &u64, _: &u64) {
+            Self::record("takeover", contract_id);
+        }
+    }
+
+    impl OnContractChanged<u64> for TestHooks {
+        fn on_beneficiary_changed(contract_id: ContractId, _: &u64, _: &u64, _: &u64) {
+            Self::record("beneficiary_changed", contract_id);
+        }
+        fn on_contract_deleted(contract_id: ContractId, _: &u64) {
+            Self::record("contract_deleted", contract_id);
+        }
+    }
+
+    /// A mock signature which is valid when it names the signer and carries the exact message.
+    #[derive(Encode, Decode, Clone, Eq, PartialEq, Debug)]
+    pub struct TestSignature(u64, Vec<u8>);
+
+    impl Verify for TestSignature {
+        type Signer = u64;
+        fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &u64) -> bool {
+            self.0 == *signer && &self.1[..] == msg.get()
+        }
+    }
+
+    fn liveness_proof(trustor: u64, nonce: u64) -> TestSignature {
+        let payload = (trustor, nonce, System::block_hash(0)).encode();
+        TestSignature(trustor, payload)
+    }
+
+    fn migration_signature(trustor: u64, new_account: u64, nonce: u64) -> TestSignature {
+        let payload = (<DefaultInstance as Instance>::PREFIX, trustor, new_account, nonce, System::block_hash(0)).encode();
+        TestSignature(new_account, payload)
+    }
+
+    /// Have account #2 take over a contract with a bond of 10, waiting out its grace period.
+    fn take_over(contract_id: ContractId) {
+        assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
+        assert_ok!(DMS::initiate_takeover(Origin::signed(2), contract_id));
+        System::set_block_number(System::block_number() + 6);
+    }
+
+    type DMS = Module<Test>;
+    type DMS1 = Module<Test, Instance1>;
+    type System = system::Module<Test>;
+    type Balances = balances::Module<Test>;
+
+    fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        build_ext_with_existential_deposit(0)
+    }
+
+    fn build_ext_with_existential_deposit(existential_deposit: u64) -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .0;
+        t.extend(
+            balances::GenesisConfig::<Test> {
+                balances: vec![(1, 50), (2, 100)],
+                vesting: Default::default(),
+                existential_deposit,
+                creation_fee: Default::default(),
+                transaction_base_fee: 1,
+                transaction_byte_fee: Default::default(),
+                transfer_fee: Default::default(),
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.extend(
+            GenesisConfig::<Test, Instance1> {
+                min_block_delay: 100,
+                max_block_delay: 1_000,
+                phantom: Default::default(),
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.into()
+    }
+
+    #[test]
+    fn act_as_trustor_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            System::set_block_number(11);
+            take_over(0);
+
+            let call = BalancesCall::transfer(2, 50);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, call));
+        });
+    }
+
+    #[test]
+    fn act_as_trustor_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            let call = BalancesCall::transfer(2, 50);
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 1, call.clone()),
+                "This contract does not exist"
+            );
+
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(1), 0, call.clone()),
+                "You cannot act as yourself"
+            );
+
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(3), 0, call.clone()),
+                "You are not the beneficiary for this trustor"
+            );
+
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, call),
+                "You cannot act as this trustor yet"
+            );
+
+            System::set_block_number(11);
+            take_over(0);
+            let call = BalancesCall::transfer(2, 51);
+            assert_err!(
+                DMS::act_as_trustor(Origin::signed(2), 0, call),
+                "balance too low to send value"
+            );
+            assert_eq!(DMS::audit_log(&1)[0].succeeded, false);
+        });
+    }
+
+    #[test]
+    fn act_as_trustor_should_respect_call_scope() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract which only allows account #2 to transfer up to 20
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::TransfersUpTo(20)));
+
+            System::set_block_number(11);
+            take_over(0);
+
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 21)),
+                "This call is outside of the contract's scope"
+            );
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 20)));
+        });
+    }
+
+    #[test]
+    fn create_contract_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            let contract = DMS::contract(0);
+            assert_eq!(contract.trustor, 1);
+            assert_eq!(contract.block_delay, 10);
+            assert_eq!(contract.execution_block, 11);
+
+            // check that account #2 has one trustor
+            assert_eq!(DMS::trustors_count(2), 1);
+
+            // check that account #1 does not have a trustor
+            assert_eq!(DMS::trustors_count(1), 0);
+
+            // check that account #1 is trustor of account #2
+            assert_eq!(DMS::trustors_by_index((2, 0)), 0);
+
+            // create a second contract to give access to account #3 after 100 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 3, 100, CallScope::All));
+
+            // check that account #1 has two contracts
+            assert_eq!(DMS::contracts_count(1), 2);
+            assert_eq!(DMS::contracts_by_index((1, 0)), 0);
+            assert_eq!(DMS::contracts_by_index((1, 1)), 1);
+            assert_eq!(DMS::next_contract_id(), 2);
+        });
+    }
+
+    #[test]
+    fn create_contract_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // check that short delay is disallowed
+            assert_noop!(
+                DMS::create_contract(Origin::signed(1), 2, 0, CallScope::All),
+                "Your block delay is too short"
+            );
+
+            // check that long delay is disallowed
+            assert_noop!(
+                DMS::create_contract(Origin::signed(1), 2, 1_000_000_001, CallScope::All),
+                "Your block delay is too long"
+            );
+
+            // check that account cannot set themselves as beneficiary
+            assert_noop!(
+                DMS::create_contract(Origin::signed(1), 1, 0, CallScope::All),
+                "You cannot use yourself as your beneficiary"
+            );
+        });
+    }
+
+    #[test]
+    fn delete_contract_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 20, CallScope::All));
+
+            assert_ok!(DMS::delete_contract(Origin::signed(1), 0));
+            assert_eq!(<Contracts<Test>>::exists(0), false);
+
+            // check that the remaining contract was moved to the front of both lists
+            assert_eq!(DMS::trustors_count(2), 1);
+            assert_eq!(DMS::trustors_by_index((2, 0)), 1);
+            assert_eq!(DMS::trustor_index(1), 0);
+            assert_eq!(DMS::contracts_count(1), 1);
+            assert_eq!(DMS::contracts_by_index((1, 0)), 1);
+            assert_eq!(DMS::contract_index(1), 0);
+
+            assert_ok!(DMS::delete_contract(Origin::signed(1), 1));
+
+            // check that account #2 does not have a trustor
+            assert_eq!(DMS::trustors_count(2), 0);
+            assert_eq!(DMS::contracts_count(1), 0);
+
+            // check that indices are cleaned up
+            assert_eq!(<TrustorsIndex<Test>>::exists(1), false);
+            assert_eq!(<TrustorsArray<Test>>::exists((2, 0)), false);
+            assert_eq!(<ContractsIndex<Test>>::exists(1), false);
+            assert_eq!(<ContractsArray<Test>>::exists((1, 0)), false);
+        });
+    }
+
+    #[test]
+    fn delete_contract_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(
+                DMS::delete_contract(Origin::signed(1), 0),
+                "This contract does not exist"
+            );
+
+            // check that only the trustor can delete a contract
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_noop!(
+                DMS::delete_contract(Origin::signed(2), 0),
+                "You are not the trustor for this contract"
+            );
+        });
+    }
+
+    #[test]
+    fn update_beneficiary_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(20), 1, 10, CallScope::All));
+
+            // update beneficiary from account #1 to account #2
+            assert_ok!(DMS::update_beneficiary(Origin::signed(20), 1, 2));
+
+            // check that account #2 has a trustor
+            assert_eq!(DMS::trustors_count(2), 1);
+
+            // check that account #1 only has one trustor
+            assert_eq!(DMS::trustors_count(1), 1);
+
+            // check that account #20 is a trustor of account #2
+            assert_eq!(DMS::trustors_by_index((2, 0)), 1);
+
+            // check that account #10 is a trustor of account #1
+            assert_eq!(DMS::trustors_by_index((1, 0)), 0);
+        });
+    }
+
+    #[test]
+    fn update_beneficiary_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(20), 1, 10, CallScope::All));
+
+            // check that the updated beneficiary needs to be different
+            assert_noop!(
+                DMS::update_beneficiary(Origin::signed(20), 1, 1),
+                "Your beneficiary is already set to this account"
+            );
+
+            // check that trustors cannot update contracts they do not own
+            assert_noop!(
+                DMS::update_beneficiary(Origin::signed(30), 1, 1),
+                "You are not the trustor for this contract"
+            );
+
+            // check that beneficiaries cannot be set to be the same as the trustor
+            assert_noop!(
+                DMS::update_beneficiary(Origin::signed(10), 0, 10),
+                "You cannot use yourself as your beneficiary"
+            );
+        });
+    }
+
+    #[test]
+    fn update_block_delay_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            // update block delay from 10 to 20
+            assert_ok!(DMS::update_block_delay(Origin::signed(10), 0, 20));
+
+            let contract = DMS::contract(0);
+            assert_eq!(contract.block_delay, 20);
+            assert_eq!(contract.execution_block, 21);
+        });
+    }
+
+    #[test]
+    fn update_block_delay_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // check that trustors without contracts cannot update block delay
+            assert_noop!(
+                DMS::update_block_delay(Origin::signed(10), 0, 10),
+                "This contract does not exist"
+            );
+
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            // check that short delay is disallowed
+            assert_noop!(
+                DMS::update_block_delay(Origin::signed(10), 0, 0),
+                "Your block delay is too short"
+            );
+
+            // check that long delay is disallowed
+            assert_noop!(
+                DMS::update_block_delay(Origin::signed(10), 0, 1_000_000_001),
+                "Your block delay is too long"
+            );
+        });
+    }
+
+    #[test]
+    fn update_call_scope_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            assert_ok!(DMS::update_call_scope(Origin::signed(10), 0, CallScope::TransfersUpTo(5)));
+
+            let contract = DMS::contract(0);
+            assert_eq!(contract.scope, CallScope::TransfersUpTo(5));
+        });
+    }
+
+    #[test]
+    fn update_call_scope_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            // check that the beneficiary cannot widen their own scope
+            assert_noop!(
+                DMS::update_call_scope(Origin::signed(1), 0, CallScope::All),
+                "You are not the trustor for this contract"
+            );
+        });
+    }
+
+    #[test]
+    fn ping_alive_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #1 after 10 and 20 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(10), 2, 20, CallScope::All));
+
+            System::set_block_number(2);
+
+            assert_ok!(DMS::ping_alive(Origin::signed(10)));
+
+            // check that every contract of the trustor was pushed back
+            assert_eq!(DMS::contract(0).execution_block, 12);
+            assert_eq!(DMS::contract(1).execution_block, 22);
+        });
+    }
+
+    #[test]
+    fn ping_alive_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // check that trustors without beneficiaries cannot ping
+            assert_noop!(
+                DMS::ping_alive(Origin::signed(10)),
+                "You do not have a current contract"
+            );
+        });
+    }
+
+    #[test]
+    fn add_ping_delegate_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // authorize account #3 to ping alive for account #1
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(1), 3));
+            assert_eq!(DMS::is_ping_delegate((1, 3)), true);
+
+            // check that the delegation is not symmetric
+            assert_eq!(DMS::is_ping_delegate((3, 1)), false);
+        });
+    }
+
+    #[test]
+    fn add_ping_delegate_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // check that account cannot delegate to themselves
+            assert_noop!(
+                DMS::add_ping_delegate(Origin::signed(1), 1),
+                "You cannot use yourself as your ping delegate"
+            );
+
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(1), 3));
+
+            // check that the same delegate cannot be added twice
+            assert_noop!(
+                DMS::add_ping_delegate(Origin::signed(1), 3),
+                "This account is already your ping delegate"
+            );
+        });
+    }
+
+    #[test]
+    fn remove_ping_delegate_should_work() {
+        with_externalities(&mut build_ext(), || {
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(1), 3));
+            assert_ok!(DMS::remove_ping_delegate(Origin::signed(1), 3));
+            assert_eq!(DMS::is_ping_delegate((1, 3)), false);
+        });
+    }
+
+    #[test]
+    fn remove_ping_delegate_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(
+                DMS::remove_ping_delegate(Origin::signed(1), 3),
+                "This account is not your ping delegate"
+            );
+        });
+    }
+
+    #[test]
+    fn ping_alive_for_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(10), 3));
+
+            System::set_block_number(5);
+
+            assert_ok!(DMS::ping_alive_for(Origin::signed(3), 10));
+
+            let contract = DMS::contract(0);
+            assert_eq!(contract.execution_block, 15);
+        });
+    }
+
+    #[test]
+    fn ping_alive_for_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            // check that accounts which are not delegates cannot ping
+            assert_noop!(
+                DMS::ping_alive_for(Origin::signed(3), 10),
+                "You are not a ping delegate for this trustor"
+            );
+
+            // check that delegates cannot ping for trustors without contracts
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(20), 3));
+            assert_noop!(
+                DMS::ping_alive_for(Origin::signed(3), 20),
+                "You do not have a current contract"
+            );
+
+            // check that a delegate has no other authority over the account
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(10), 3));
+            System::set_block_number(11);
+            let call = BalancesCall::transfer(3, 1);
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(3), 0, call),
+                "You are not the beneficiary for this trustor"
+            );
+        });
+    }
+
+    #[test]
+    fn submit_liveness_proof_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            System::set_block_number(5);
+
+            // any relayer can submit the proof signed by the trustor
+            let call = Call::submit_liveness_proof(10, 0, liveness_proof(10, 0));
+            match DMS::validate_unsigned(&call) {
+                TransactionValidity::Valid { .. } => {}
+                _ => panic!("liveness proof should be valid"),
+            }
+            assert_ok!(DMS::submit_liveness_proof(Origin::INHERENT, 10, 0, liveness_proof(10, 0)));
+
+            let contract = DMS::contract(0);
+            assert_eq!(contract.execution_block, 15);
+            assert_eq!(DMS::liveness_proof_nonce(10), 1);
+        });
+    }
+
+    #[test]
+    fn submit_liveness_proof_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            // check that the proof must be submitted unsigned
+            assert_noop!(
+                DMS::submit_liveness_proof(Origin::signed(3), 10, 0, liveness_proof(10, 0)),
+                "bad origin: expected to be an inherent"
+            );
+
+            // check that the proof must be signed by the trustor
+            assert_noop!(
+                DMS::submit_liveness_proof(Origin::INHERENT, 10, 0, liveness_proof(3, 0)),
+                "Your liveness proof signature is invalid"
+            );
+            let call = Call::submit_liveness_proof(10, 0, liveness_proof(3, 0));
+            assert_eq!(
+                DMS::validate_unsigned(&call),
+                TransactionValidity::Invalid(ApplyError::BadSignature as i8)
+            );
+
+            // check that a proof cannot be replayed
+            assert_ok!(DMS::submit_liveness_proof(Origin::INHERENT, 10, 0, liveness_proof(10, 0)));
+            assert_noop!(
+                DMS::submit_liveness_proof(Origin::INHERENT, 10, 0, liveness_proof(10, 0)),
+                "Your liveness proof nonce is invalid"
+            );
+            let call = Call::submit_liveness_proof(10, 0, liveness_proof(10, 0));
+            assert_eq!(
+                DMS::validate_unsigned(&call),
+                TransactionValidity::Invalid(ApplyError::Stale as i8)
+            );
+
+            // check that other calls cannot be submitted unsigned
+            assert_eq!(
+                DMS::validate_unsigned(&Call::ping_alive()),
+                TransactionValidity::Invalid(ApplyError::BadSignature as i8)
+            );
+        });
+    }
+
+    #[test]
+    fn ping_alive_fee_should_be_refunded() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            // check that the first ping of the period is refunded
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(Balances::free_balance(&1), 51);
+            assert_eq!(DMS::fee_exempt_pings_used(1), (0, 1));
+
+            // check that the quota for the period is enforced
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(Balances::free_balance(&1), 51);
+
+            // check that the quota is reset in the next period
+            System::set_block_number(14_400);
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(Balances::free_balance(&1), 52);
+            assert_eq!(DMS::fee_exempt_pings_used(1), (1, 1));
+
+            // check that delegates are refunded from their own quota
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(1), 2));
+            assert_ok!(DMS::ping_alive_for(Origin::signed(2), 1));
+            assert_eq!(Balances::free_balance(&2), 101);
+        });
+    }
+
+    #[test]
+    fn is_liveness_call_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(1), 3));
+
+            assert!(DMS::is_liveness_call(&1, &Call::ping_alive()));
+            assert!(DMS::is_liveness_call(&3, &Call::ping_alive_for(1)));
+            assert!(!DMS::is_liveness_call(&1, &Call::delete_contract(0)));
+
+            // check that accounts which cannot ping for anyone get no priority
+            assert!(!DMS::is_liveness_call(&2, &Call::ping_alive()));
+            assert!(!DMS::is_liveness_call(&4, &Call::ping_alive_for(1)));
+            assert!(!DMS::is_liveness_call(&1, &Call::ping_alive_for(2)));
+        });
+    }
+
+    #[test]
+    fn update_handover_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+            assert_eq!(DMS::contract(0).handover, Handover::Takeover);
+
+            assert_ok!(DMS::update_handover(Origin::signed(10), 0, Handover::Vesting(100)));
+            assert_eq!(DMS::contract(0).handover, Handover::Vesting(100));
+        });
+    }
+
+    #[test]
+    fn update_handover_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create contract to give access to account #1 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10, CallScope::All));
+
+            assert_noop!(
+                DMS::update_handover(Origin::signed(1), 0, Handover::Vesting(100)),
+                "You are not the trustor for this contract"
+            );
+
+            assert_noop!(
+                DMS::update_handover(Origin::signed(10), 0, Handover::Vesting(0)),
+                "Your vesting period is too short"
+            );
+        });
+    }
+
+    #[test]
+    fn claim_vested_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to release account #1's balance to account #2 over 10 blocks
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::update_handover(Origin::signed(1), 0, Handover::Vesting(10)));
+
+            // check that half of the balance is released halfway through the schedule
+            System::set_block_number(16);
+            assert_ok!(DMS::claim_vested(Origin::signed(2), 0, 2));
+            assert_eq!(Balances::free_balance(&1), 25);
+            assert_eq!(Balances::free_balance(&2), 125);
+
+            let schedule = DMS::vesting_schedule(0).unwrap();
+            assert_eq!(schedule.total, 50);
+            assert_eq!(schedule.claimed, 25);
+            assert_eq!(schedule.starting_block, 11);
+
+            // check that the rest is released at the end of the schedule
+            System::set_block_number(30);
+            assert_ok!(DMS::claim_vested(Origin::signed(2), 0, 2));
+            assert_eq!(Balances::free_balance(&1), 0);
+            assert_eq!(Balances::free_balance(&2), 150);
+        });
+    }
+
+    #[test]
+    fn claim_vested_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            System::set_block_number(16);
+            assert_noop!(
+                DMS::claim_vested(Origin::signed(2), 0, 2),
+                "This contract does not release funds through vesting"
+            );
+
+            assert_ok!(DMS::update_handover(Origin::signed(1), 0, Handover::Vesting(10)));
+
+            // check that a vesting contract cannot be taken over
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 1)),
+                "This contract releases funds through vesting"
+            );
+
+            assert_noop!(
+                DMS::claim_vested(Origin::signed(3), 0, 3),
+                "You are not the beneficiary for this trustor"
+            );
+
+            // check that pinging alive stops the vesting
+            assert_ok!(DMS::claim_vested(Origin::signed(2), 0, 2));
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::vesting_schedule(0), None);
+            assert_noop!(
+                DMS::claim_vested(Origin::signed(2), 0, 2),
+                "You cannot act as this trustor yet"
+            );
+
+            // check that nothing can be claimed at the start of the schedule
+            System::set_block_number(26);
+            assert_noop!(
+                DMS::claim_vested(Origin::signed(2), 0, 2),
+                "There is no vested balance to claim"
+            );
+        });
+    }
+
+    #[test]
+    fn deposit_escrow_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 10));
+
+            assert_eq!(DMS::escrow(0), 30);
+            assert_eq!(Balances::free_balance(&1), 20);
+            assert_eq!(Balances::reserved_balance(&1), 30);
+
+            // check that deleting the contract returns the escrow
+            assert_ok!(DMS::delete_contract(Origin::signed(1), 0));
+            assert_eq!(Balances::free_balance(&1), 50);
+            assert_eq!(<Escrows<Test>>::exists(0), false);
+        });
+    }
+
+    #[test]
+    fn deposit_escrow_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_noop!(
+                DMS::deposit_escrow(Origin::signed(2), 0, 20),
+                "You are not the trustor for this contract"
+            );
+
+            assert!(DMS::deposit_escrow(Origin::signed(1), 0, 51).is_err());
+            assert_eq!(DMS::escrow(0), 0);
+        });
+    }
+
+    #[test]
+    fn withdraw_escrow_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
+
+            // check that the trustor can withdraw while the contract is not executable
+            assert_ok!(DMS::withdraw_escrow(Origin::signed(1), 0, 1, 5));
+            assert_eq!(Balances::free_balance(&1), 35);
+            assert_eq!(DMS::escrow(0), 15);
+
+            // check that the beneficiary can withdraw once the contract is taken over
+            System::set_block_number(11);
+            take_over(0);
+            assert_ok!(DMS::withdraw_escrow(Origin::signed(2), 0, 2, 15));
+            assert_eq!(Balances::free_balance(&2), 115);
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert_eq!(DMS::escrow(0), 0);
+        });
+    }
+
+    #[test]
+    fn withdraw_escrow_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(1), 0, 1, 5),
+                "This contract does not exist"
+            );
+
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
+
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(1), 0, 1, 21),
+                "The escrow of this contract is too low"
+            );
+
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(3), 0, 3, 5),
+                "You are not a party to this contract"
+            );
+
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(1), 0, 2, 5),
+                "You can only withdraw the escrow into your own account"
+            );
+
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(2), 0, 2, 5),
+                "You cannot act as this trustor yet"
+            );
+
+            System::set_block_number(11);
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(1), 0, 1, 5),
+                "Your contract is already executable"
+            );
+
+            // check that the beneficiary must wait out the grace period of a takeover
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(2), 0, 2, 5),
+                "You have not initiated a takeover of this contract"
+            );
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
+            assert_ok!(DMS::initiate_takeover(Origin::signed(2), 0));
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(2), 0, 2, 5),
+                "The grace period of this takeover is not over"
+            );
+        });
+    }
+
+    #[test]
+    fn manage_attesters_should_work() {
+        with_externalities(&mut build_ext(), || {
+            assert_ok!(DMS::add_attester(Origin::ROOT, 5));
+            assert_eq!(DMS::is_attester(5), true);
+
+            assert_ok!(DMS::remove_attester(Origin::ROOT, 5));
+            assert_eq!(DMS::is_attester(5), false);
+
+            assert_ok!(DMS::set_attestation_quorum(Origin::ROOT, 3, 50));
+            assert_eq!(DMS::attestation_quorum(), 3);
+            assert_eq!(DMS::attested_block_delay(), 50);
+        });
+    }
+
+    #[test]
+    fn manage_attesters_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(DMS::add_attester(Origin::signed(1), 5), "bad origin: expected to be a root origin");
+            assert_noop!(DMS::remove_attester(Origin::ROOT, 5), "This account is not an attester");
+            assert_noop!(DMS::set_attestation_quorum(Origin::ROOT, 0, 50), "The attestation quorum must not be zero");
+
+            assert_ok!(DMS::add_attester(Origin::ROOT, 5));
+            assert_noop!(DMS::add_attester(Origin::ROOT, 5), "This account is already an attester");
+        });
+    }
+
+    #[test]
+    fn attest_death_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 1000 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 1000, CallScope::All));
+            assert_ok!(DMS::add_attester(Origin::ROOT, 5));
+            assert_ok!(DMS::add_attester(Origin::ROOT, 6));
+
+            // check that a single attestation does not shorten the contract
+            assert_ok!(DMS::attest_death(Origin::signed(5), 1));
+            assert_eq!(DMS::contract(0).execution_block, 1001);
+
+            // check that a quorum of attestations shortens the contract
+            assert_ok!(DMS::attest_death(Origin::signed(6), 1));
+            assert_eq!(DMS::attestations(1), vec![5, 6]);
+            assert_eq!(DMS::contract(0).execution_block, 101);
+
+            // check that the trustor can void the attestations by pinging alive
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::contract(0).execution_block, 1001);
+            assert_eq!(<Attestations<Test>>::exists(1), false);
+        });
+    }
+
+    #[test]
+    fn attest_death_should_work_after_lowering_quorum() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 1000 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 1000, CallScope::All));
+            assert_ok!(DMS::set_attestation_quorum(Origin::ROOT, 3, 100));
+            for attester in 5..9 {
+                assert_ok!(DMS::add_attester(Origin::ROOT, attester));
+            }
+
+            assert_ok!(DMS::attest_death(Origin::signed(5), 1));
+            assert_ok!(DMS::attest_death(Origin::signed(6), 1));
+            assert_ok!(DMS::set_attestation_quorum(Origin::ROOT, 2, 100));
+            assert_eq!(DMS::quorum_reached(1), false);
+
+            // check that the quorum is reached by an attestation above it
+            assert_ok!(DMS::attest_death(Origin::signed(7), 1));
+            assert_eq!(DMS::quorum_reached(1), true);
+            assert_eq!(DMS::contract(0).execution_block, 101);
+
+            // check that further attestations do not shorten the contract again
+            System::set_block_number(50);
+            assert_ok!(DMS::set_attestation_quorum(Origin::ROOT, 2, 10));
+            assert_ok!(DMS::attest_death(Origin::signed(8), 1));
+            assert_eq!(DMS::contract(0).execution_block, 101);
+
+            // check that pinging alive resets the quorum
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::quorum_reached(1), false);
+        });
+    }
+
+    #[test]
+    fn attest_death_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 1000 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 1000, CallScope::All));
+
+            assert_noop!(DMS::attest_death(Origin::signed(5), 1), "You are not an attester");
+
+            assert_ok!(DMS::add_attester(Origin::ROOT, 5));
+            assert_noop!(DMS::attest_death(Origin::signed(5), 3), "You selected a trustor without a contract");
+
+            assert_ok!(DMS::attest_death(Origin::signed(5), 1));
+            assert_noop!(
+                DMS::attest_death(Origin::signed(5), 1),
+                "You have already attested to this trustor's death"
+            );
+        });
+    }
+
+    #[test]
+    fn revoke_attestation_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 1000 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 1000, CallScope::All));
+            assert_ok!(DMS::add_attester(Origin::ROOT, 5));
+            assert_ok!(DMS::add_attester(Origin::ROOT, 6));
+
+            assert_ok!(DMS::attest_death(Origin::signed(5), 1));
+            assert_ok!(DMS::revoke_attestation(Origin::signed(5), 1));
+            assert_eq!(DMS::attestations(1), Vec::<u64>::new());
+
+            // check that a revoked attestation does not count towards the quorum
+            assert_ok!(DMS::attest_death(Origin::signed(6), 1));
+            assert_eq!(DMS::contract(0).execution_block, 1001);
+        });
+    }
+
+    #[test]
+    fn revoke_attestation_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(
+                DMS::revoke_attestation(Origin::signed(5), 1),
+                "You have not attested to this trustor's death"
+            );
+        });
+    }
+
+    #[test]
+    fn trigger_now_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            // check that the contract is executable right away without a confirmation delay
+            assert_ok!(DMS::trigger_now(Origin::signed(1), 0, 0));
+            assert_eq!(DMS::contract(0).execution_block, 1);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)));
+
+            // check that a confirmed trigger cannot be undone with the trustor's key
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::contract(0).execution_block, 1);
+            assert_noop!(
+                DMS::delete_contract(Origin::signed(1), 0),
+                "Your contract was triggered and can no longer be changed"
+            );
+        });
+    }
+
+    #[test]
+    fn trigger_now_should_be_cancellable_before_confirmation() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_ok!(DMS::trigger_now(Origin::signed(1), 0, 3));
+            assert_eq!(DMS::contract(0).execution_block, 4);
+
+            System::set_block_number(2);
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+
+            let contract = DMS::contract(0);
+            assert_eq!(contract.triggered, false);
+            assert_eq!(contract.execution_block, 12);
+        });
+    }
+
+    #[test]
+    fn trigger_now_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_noop!(
+                DMS::trigger_now(Origin::signed(2), 0, 0),
+                "You are not the trustor for this contract"
+            );
+
+            assert_ok!(DMS::trigger_now(Origin::signed(1), 0, 3));
+            assert_noop!(
+                DMS::trigger_now(Origin::signed(1), 0, 0),
+                "Your contract was already triggered"
+            );
+        });
+    }
+
+    #[test]
+    fn ping_alive_with_token_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            // check that tokens are only recorded for trustors with a duress commitment
+            assert_ok!(DMS::ping_alive_with_token(Origin::signed(1), H256::from([1; 32])));
+            assert_eq!(DMS::ping_tokens_count(1), 0);
+
+            let secret = H256::from([7; 32]);
+            assert_ok!(DMS::set_duress_commitment(Origin::signed(1), Some(BlakeTwo256::hash_of(&secret))));
+
+            System::set_block_number(2);
+            assert_ok!(DMS::ping_alive_with_token(Origin::signed(1), H256::from([1; 32])));
+            assert_eq!(DMS::ping_token((1, 0)), Some(H256::from([1; 32])));
+            assert_eq!(DMS::contract(0).execution_block, 12);
+
+            // check that a duress ping pushes the contract back like a normal ping
+            let token = BlakeTwo256::hash_of(&(secret, 1u64));
+            assert_ok!(DMS::ping_alive_with_token(Origin::signed(1), token));
+            assert_eq!(DMS::ping_tokens_count(1), 2);
+            assert_eq!(DMS::contract(0).execution_block, 12);
+        });
+    }
+
+    #[test]
+    fn ping_alive_with_token_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            let secret = H256::from([7; 32]);
+            assert_ok!(DMS::set_duress_commitment(Origin::signed(1), Some(BlakeTwo256::hash_of(&secret))));
+
+            // check that pings without a token are rejected once a commitment is set
+            assert_noop!(
+                DMS::ping_alive(Origin::signed(1)),
+                "Your pings must include a token"
+            );
+
+            assert_noop!(
+                DMS::ping_alive_with_token(Origin::signed(3), H256::from([1; 32])),
+                "You do not have a current contract"
+            );
+        });
+    }
+
+    #[test]
+    fn claim_duress_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            let secret = H256::from([7; 32]);
+            assert_ok!(DMS::set_duress_commitment(Origin::signed(1), Some(BlakeTwo256::hash_of(&secret))));
+            assert_ok!(DMS::ping_alive_with_token(Origin::signed(1), BlakeTwo256::hash_of(&(secret, 0u64))));
+
+            System::set_block_number(5);
+            assert_ok!(DMS::claim_duress(Origin::signed(2), 0, secret, 0));
+            assert_eq!(DMS::contract(0).execution_block, 5);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)));
+
+            // check that the contract cannot be pinged back
+            assert_ok!(DMS::ping_alive_with_token(Origin::signed(1), H256::from([1; 32])));
+            assert_eq!(DMS::contract(0).execution_block, 5);
+        });
+    }
+
+    #[test]
+    fn claim_duress_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            let secret = H256::from([7; 32]);
+            assert_noop!(
+                DMS::claim_duress(Origin::signed(2), 0, secret, 0),
+                "Your trustor does not have a duress commitment"
+            );
+
+            assert_ok!(DMS::set_duress_commitment(Origin::signed(1), Some(BlakeTwo256::hash_of(&secret))));
+            assert_ok!(DMS::ping_alive_with_token(Origin::signed(1), H256::from([1; 32])));
+
+            assert_noop!(
+                DMS::claim_duress(Origin::signed(3), 0, secret, 0),
+                "You are not the beneficiary for this trustor"
+            );
+
+            assert_noop!(
+                DMS::claim_duress(Origin::signed(2), 0, H256::from([8; 32]), 0),
+                "Your duress secret is invalid"
+            );
+
+            assert_noop!(
+                DMS::claim_duress(Origin::signed(2), 0, secret, 0),
+                "This ping was not made under duress"
+            );
+        });
+    }
+
+    #[test]
+    fn pause_contract_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            // check that the remaining 6 blocks are kept until the pause ends
+            System::set_block_number(5);
+            assert_ok!(DMS::pause_contract(Origin::signed(1), 0, 105));
+            assert_eq!(DMS::contract(0).execution_block, 111);
+
+            // check that pinging during the pause does not bring the contract forward
+            System::set_block_number(50);
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::contract(0).execution_block, 111);
+
+            // check that pings work as usual after the pause
+            System::set_block_number(105);
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::contract(0).execution_block, 115);
+        });
+    }
+
+    #[test]
+    fn pause_contract_twice_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            System::set_block_number(5);
+            assert_ok!(DMS::pause_contract(Origin::signed(1), 0, 50));
+            assert_eq!(DMS::contract(0).execution_block, 56);
+
+            // check that a running pause cannot be replaced
+            System::set_block_number(20);
+            assert_noop!(
+                DMS::pause_contract(Origin::signed(1), 0, 60),
+                "Your contract is already paused"
+            );
+
+            // check that only the remaining 3 blocks are kept by the second pause
+            System::set_block_number(53);
+            assert_ok!(DMS::pause_contract(Origin::signed(1), 0, 90));
+            assert_eq!(DMS::contract(0).execution_block, 93);
+        });
+    }
+
+    #[test]
+    fn pause_contract_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_noop!(
+                DMS::pause_contract(Origin::signed(2), 0, 100),
+                "You are not the trustor for this contract"
+            );
+
+            assert_noop!(
+                DMS::pause_contract(Origin::signed(1), 0, 1),
+                "Your pause must end in the future"
+            );
+
+            assert_ok!(DMS::set_max_pause_duration(Origin::ROOT, 50));
+            assert_noop!(
+                DMS::pause_contract(Origin::signed(1), 0, 52),
+                "Your pause is too long"
+            );
+
+            assert_ok!(DMS::trigger_now(Origin::signed(1), 0, 3));
+            assert_noop!(
+                DMS::pause_contract(Origin::signed(1), 0, 10),
+                "Your contract was triggered"
+            );
+        });
+    }
+
+    #[test]
+    fn force_cancel_contract_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
+
+            assert_ok!(DMS::force_cancel_contract(Origin::ROOT, 0));
+
+            assert_eq!(<Contracts<Test>>::exists(0), false);
+            assert_eq!(DMS::trustors_count(2), 0);
+            assert_eq!(DMS::contracts_count(1), 0);
+            assert_eq!(Balances::free_balance(&1), 50);
+        });
+    }
+
+    #[test]
+    fn force_execute_contract_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_ok!(DMS::force_execute_contract(Origin::ROOT, 0));
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)));
+
+            // check that the trustor cannot ping the contract back
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::contract(0).execution_block, 1);
+        });
+    }
+
+    #[test]
+    fn force_set_beneficiary_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_ok!(DMS::force_set_beneficiary(Origin::ROOT, 0, 3));
+
+            assert_eq!(DMS::contract(0).beneficiary, 3);
+            assert_eq!(DMS::trustors_count(2), 0);
+            assert_eq!(DMS::trustors_count(3), 1);
+            assert_eq!(DMS::trustors_by_index((3, 0)), 0);
+        });
+    }
+
+    #[test]
+    fn force_calls_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_noop!(
+                DMS::force_cancel_contract(Origin::signed(1), 0),
+                "bad origin: expected to be a root origin"
+            );
+            assert_noop!(
+                DMS::force_execute_contract(Origin::signed(2), 0),
+                "bad origin: expected to be a root origin"
+            );
+            assert_noop!(
+                DMS::force_set_beneficiary(Origin::signed(2), 0, 3),
+                "bad origin: expected to be a root origin"
+            );
+
+            assert_noop!(DMS::force_cancel_contract(Origin::ROOT, 1), "This contract does not exist");
+            assert_noop!(DMS::force_execute_contract(Origin::ROOT, 1), "This contract does not exist");
+            assert_noop!(
+                DMS::force_set_beneficiary(Origin::ROOT, 0, 1),
+                "You cannot use yourself as your beneficiary"
+            );
+        });
+    }
+
+    #[test]
+    fn manage_jurors_should_work() {
+        with_externalities(&mut build_ext(), || {
+            assert_ok!(DMS::add_juror(Origin::ROOT, 5));
+            assert_eq!(DMS::is_juror(5), true);
+
+            assert_ok!(DMS::remove_juror(Origin::ROOT, 5));
+            assert_eq!(DMS::is_juror(5), false);
+
+            assert_noop!(DMS::add_juror(Origin::signed(1), 5), "bad origin: expected to be a root origin");
+            assert_noop!(DMS::remove_juror(Origin::ROOT, 5), "This account is not a juror");
+        });
+    }
+
+    #[test]
+    fn open_dispute_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+
+            assert_ok!(DMS::open_dispute(Origin::signed(1), 0));
+            assert_eq!(Balances::reserved_balance(&1), 10);
+            assert_eq!(DMS::dispute(0).unwrap().deadline, 6);
+
+            // check that the takeover is frozen during the dispute
+            System::set_block_number(11);
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)),
+                "This contract is under dispute"
+            );
+        });
+    }
+
+    #[test]
+    fn open_dispute_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(DMS::open_dispute(Origin::signed(1), 0), "This contract does not exist");
+
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+
+            assert_noop!(DMS::open_dispute(Origin::signed(3), 0), "You are not a party to this contract");
+
+            assert_ok!(DMS::open_dispute(Origin::signed(2), 0));
+            assert_noop!(DMS::open_dispute(Origin::signed(1), 0), "This contract is under dispute");
+        });
+    }
+
+    #[test]
+    fn vote_dispute_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 5));
+
+            assert_noop!(
+                DMS::vote_dispute(Origin::signed(5), 0, Verdict::CancelContract),
+                "This contract is not under dispute"
+            );
+
+            assert_ok!(DMS::open_dispute(Origin::signed(1), 0));
+
+            assert_noop!(
+                DMS::vote_dispute(Origin::signed(6), 0, Verdict::CancelContract),
+                "You are not a juror"
+            );
+            assert_noop!(
+                DMS::vote_dispute(Origin::signed(5), 0, Verdict::ReassignBeneficiary(1)),
+                "You cannot reassign a contract to its trustor"
+            );
+
+            assert_ok!(DMS::vote_dispute(Origin::signed(5), 0, Verdict::CancelContract));
+            assert_noop!(
+                DMS::vote_dispute(Origin::signed(5), 0, Verdict::UpholdTakeover),
+                "You have already voted on this dispute"
+            );
+
+            assert_ok!(DMS::add_juror(Origin::ROOT, 6));
+            System::set_block_number(7);
+            assert_noop!(
+                DMS::vote_dispute(Origin::signed(6), 0, Verdict::CancelContract),
+                "The voting period of this dispute is over"
+            );
+        });
+    }
+
+    #[test]
+    fn resolve_dispute_should_cancel_contract() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 6));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 7));
+
+            assert_ok!(DMS::open_dispute(Origin::signed(1), 0));
+            assert_ok!(DMS::vote_dispute(Origin::signed(5), 0, Verdict::CancelContract));
+            assert_ok!(DMS::vote_dispute(Origin::signed(6), 0, Verdict::CancelContract));
+            assert_ok!(DMS::vote_dispute(Origin::signed(7), 0, Verdict::UpholdTakeover));
+
+            assert_noop!(
+                DMS::resolve_dispute(Origin::signed(3), 0),
+                "The dispute is still open for voting"
+            );
+
+            System::set_block_number(7);
+            assert_ok!(DMS::resolve_dispute(Origin::signed(3), 0));
+
+            // check that the contract is gone and the bond is refunded
+            assert_eq!(<Contracts<Test>>::exists(0), false);
+            assert_eq!(DMS::dispute(0), None);
+            assert_eq!(Balances::free_balance(&1), 50);
+            assert_eq!(Balances::reserved_balance(&1), 0);
+        });
+    }
+
+    #[test]
+    fn resolve_dispute_should_reassign_beneficiary() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 5));
+
+            // check that the beneficiary loses their bond to the trustor when the verdict goes against them
+            assert_ok!(DMS::open_dispute(Origin::signed(2), 0));
+            assert_ok!(DMS::vote_dispute(Origin::signed(5), 0, Verdict::ReassignBeneficiary(3)));
+
+            System::set_block_number(7);
+            assert_ok!(DMS::resolve_dispute(Origin::signed(3), 0));
+
+            assert_eq!(DMS::contract(0).beneficiary, 3);
+            assert_eq!(DMS::trustors_count(2), 0);
+            assert_eq!(DMS::trustors_by_index((3, 0)), 0);
+            assert_eq!(Balances::free_balance(&2), 90);
+            assert_eq!(Balances::free_balance(&1), 60);
+        });
+    }
+
+    #[test]
+    fn resolve_dispute_should_keep_contract_on_tie() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 6));
+
+            assert_ok!(DMS::open_dispute(Origin::signed(1), 0));
+            assert_ok!(DMS::vote_dispute(Origin::signed(5), 0, Verdict::CancelContract));
+            assert_ok!(DMS::vote_dispute(Origin::signed(6), 0, Verdict::UpholdTakeover));
+
+            System::set_block_number(11);
+            assert_ok!(DMS::resolve_dispute(Origin::signed(3), 0));
+
+            assert_eq!(<Contracts<Test>>::exists(0), true);
+            assert_eq!(Balances::free_balance(&1), 50);
+            take_over(0);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)));
+        });
+    }
+
+    #[test]
+    fn resolve_dispute_should_clear_votes() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_dispute_parameters(Origin::ROOT, 10, 5));
+            assert_ok!(DMS::add_juror(Origin::ROOT, 5));
+
+            assert_ok!(DMS::open_dispute(Origin::signed(1), 0));
+            assert_ok!(DMS::vote_dispute(Origin::signed(5), 0, Verdict::UpholdTakeover));
+            assert_noop!(
+                DMS::update_beneficiary(Origin::signed(1), 0, 3),
+                "This contract is under dispute"
+            );
+            assert_noop!(
+                DMS::update_call_scope(Origin::signed(1), 0, CallScope::TransfersUpTo(10)),
+                "This contract is under dispute"
+            );
+
+            System::set_block_number(7);
+            assert_ok!(DMS::resolve_dispute(Origin::signed(3), 0));
+            assert_eq!(DMS::dispute_vote((0, 5)), None);
+
+            // check that the juror can vote on a second dispute over the same contract
+            assert_ok!(DMS::open_dispute(Origin::signed(1), 0));
+            assert_ok!(DMS::vote_dispute(Origin::signed(5), 0, Verdict::CancelContract));
+
+            System::set_block_number(13);
+            assert_ok!(DMS::resolve_dispute(Origin::signed(3), 0));
+            assert_eq!(<Contracts<Test>>::exists(0), false);
+            assert_eq!(DMS::dispute_vote((0, 5)), None);
+        });
+    }
+
+    #[test]
+    fn initiate_takeover_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
+
+            System::set_block_number(11);
+            assert_ok!(DMS::initiate_takeover(Origin::signed(2), 0));
+            assert_eq!(Balances::reserved_balance(&2), 10);
+            assert_eq!(DMS::takeover(0).unwrap().grace_end, 16);
+
+            let call = BalancesCall::transfer(2, 10);
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, call.clone()),
+                "The grace period of this takeover is not over"
+            );
+
+            // check that the bond is refunded once the beneficiary acts as the trustor
+            System::set_block_number(17);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, call.clone()));
+            assert_eq!(Balances::reserved_balance(&2), 0);
+            assert_eq!(Balances::free_balance(&2), 110);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, call));
+        });
+    }
+
+    #[test]
+    fn initiate_takeover_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(DMS::initiate_takeover(Origin::signed(2), 0), "This contract does not exist");
+
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
+
+            assert_noop!(
+                DMS::initiate_takeover(Origin::signed(3), 0),
+                "You are not the beneficiary for this trustor"
+            );
+            assert_noop!(
+                DMS::initiate_takeover(Origin::signed(2), 0),
+                "You cannot act as this trustor yet"
+            );
+
+            System::set_block_number(11);
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)),
+                "You have not initiated a takeover of this contract"
+            );
+
+            assert_ok!(DMS::initiate_takeover(Origin::signed(2), 0));
+            assert_noop!(
+                DMS::initiate_takeover(Origin::signed(2), 0),
+                "A takeover of this contract was already initiated"
+            );
+        });
+    }
+
+    #[test]
+    fn veto_takeover_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
 
             System::set_block_number(11);
+            assert_ok!(DMS::initiate_takeover(Origin::signed(2), 0));
 
-            let call = BalancesCall::transfer(2, 50);
-            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 1, call));
+            // check that the bond is slashed to the trustor
+            assert_ok!(DMS::veto_takeover(Origin::signed(1), 0));
+            assert_eq!(DMS::takeover(0), None);
+            assert_eq!(DMS::contract(0).execution_block, 21);
+            assert_eq!(Balances::free_balance(&1), 60);
+            assert_eq!(Balances::free_balance(&2), 90);
+            assert_eq!(Balances::reserved_balance(&2), 0);
         });
     }
 
     #[test]
-    fn act_as_trustor_should_fail() {
+    fn veto_takeover_should_fail() {
         with_externalities(&mut build_ext(), || {
             // create a contract to give access to account #2 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
 
-            let call = BalancesCall::transfer(2, 50);
+            assert_noop!(DMS::veto_takeover(Origin::signed(1), 0), "There is no takeover of this contract");
+            assert_noop!(DMS::veto_takeover(Origin::signed(2), 0), "You are not the trustor for this contract");
+
+            System::set_block_number(11);
+            assert_ok!(DMS::initiate_takeover(Origin::signed(2), 0));
+
+            System::set_block_number(17);
+            assert_noop!(DMS::veto_takeover(Origin::signed(1), 0), "The grace period of this takeover is over");
+        });
+    }
+
+    #[test]
+    fn ping_alive_should_slash_takeover_bond() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 10, 5));
+
+            System::set_block_number(11);
+            assert_ok!(DMS::initiate_takeover(Origin::signed(2), 0));
+
+            // check that the bond is slashed to the trustor, on top of the refunded ping fee
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::takeover(0), None);
+            assert_eq!(Balances::free_balance(&1), 61);
+            assert_eq!(Balances::free_balance(&2), 90);
+        });
+    }
+
+    #[test]
+    fn audit_log_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            System::set_block_number(11);
+            take_over(0);
+
+            let call = BalancesCall::transfer(2, 10);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, call.clone()));
+            assert_eq!(
+                DMS::audit_log(&1),
+                vec![AuditEntry {
+                    block_number: 17,
+                    contract_id: 0,
+                    beneficiary: 2,
+                    call_hash: BlakeTwo256::hash_of(&call),
+                    succeeded: true,
+                }]
+            );
+
+            // check that only the most recent entries are kept
+            for _ in 0..AUDIT_LOG_LENGTH {
+                assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 0)));
+            }
+            assert_eq!(DMS::audit_log_count(1), AUDIT_LOG_LENGTH + 1);
+            assert_eq!(DMS::audit_log(&1).len() as u64, AUDIT_LOG_LENGTH);
+            assert_eq!(DMS::audit_entry((1, 0)), None);
+        });
+    }
+
+    #[test]
+    fn hooks_should_be_called() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            assert_ok!(DMS::update_beneficiary(Origin::signed(1), 1, 3));
+            assert_ok!(DMS::delete_contract(Origin::signed(1), 1));
+
+            System::set_block_number(11);
+            take_over(0);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)));
+
+            assert_eq!(
+                TestHooks::calls(),
+                vec![
+                    ("beneficiary_changed", 1),
+                    ("contract_deleted", 1),
+                    ("switch_fired", 0),
+                    ("takeover", 0),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn instances_should_be_independent() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+
+            // check that the other instance has its own contracts and bounds
+            assert_eq!(DMS1::contracts_count(1), 0);
+            assert_noop!(
+                DMS1::create_contract(Origin::signed(1), 2, 10, CallScope::All),
+                "Your block delay is too short"
+            );
+            assert_ok!(DMS1::create_contract(Origin::signed(1), 2, 100, CallScope::All));
+            assert_eq!(DMS1::contract(0).block_delay, 100);
+            assert_eq!(DMS::contract(0).block_delay, 10);
+
+            // check that a ping only applies to the contracts of its own instance
+            System::set_block_number(5);
+            assert_ok!(DMS1::ping_alive(Origin::signed(1)));
+            assert_eq!(DMS::contract(0).execution_block, 11);
+            assert_eq!(DMS1::contract(0).execution_block, 105);
+        });
+    }
+
+    #[test]
+    fn migrate_contract_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #2 and #4 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 4, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 1, 20));
+
+            System::set_block_number(5);
+            let signature = migration_signature(1, 3, 0);
+            assert_ok!(DMS::migrate_contract(Origin::signed(1), 3, signature, true));
+
+            // check that the contracts moved without changing the beneficiaries' lists
+            assert_eq!(DMS::contracts_count(1), 0);
+            assert_eq!(DMS::contracts_count(3), 2);
+            assert_eq!(DMS::contract(0).trustor, 3);
+            assert_eq!(DMS::contract(1).trustor, 3);
+            assert_eq!(DMS::trustors_by_index((2, 0)), 0);
+            assert_eq!(DMS::trustors_by_index((4, 0)), 1);
+            assert_eq!(DMS::contract(0).execution_block, 15);
+
+            // check that the balance and escrow moved to the new account
+            assert_eq!(Balances::free_balance(&1), 0);
+            assert_eq!(Balances::free_balance(&3), 30);
+            assert_eq!(Balances::reserved_balance(&3), 20);
+
+            assert_ok!(DMS::delete_contract(Origin::signed(3), 1));
+            assert_eq!(Balances::free_balance(&3), 50);
+        });
+    }
+
+    #[test]
+    fn migrate_contract_should_reject_replayed_signatures() {
+        with_externalities(&mut build_ext(), || {
+            // create contracts to give access to account #2 on both switches
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS1::create_contract(Origin::signed(1), 2, 100, CallScope::All));
+
+            let signature = migration_signature(1, 3, 0);
+            assert_ok!(DMS::migrate_contract(Origin::signed(1), 3, signature.clone(), false));
+            assert_eq!(DMS::migration_nonce(3), 1);
+
+            // check that the consent cannot move the contracts on the other switch
             assert_noop!(
-                DMS::act_as_trustor(Origin::signed(2), 3, call.clone()),
-                "You selected a trustor without a contract"
+                DMS1::migrate_contract(Origin::signed(1), 3, signature.clone(), false),
+                "Your migration signature is invalid"
             );
 
+            // check that the consent cannot be used again once the contracts moved back
+            assert_ok!(DMS::migrate_contract(Origin::signed(3), 1, migration_signature(3, 1, 0), false));
             assert_noop!(
-                DMS::act_as_trustor(Origin::signed(1), 1, call.clone()),
-                "You cannot act as yourself"
+                DMS::migrate_contract(Origin::signed(1), 3, signature, false),
+                "Your migration signature is invalid"
             );
+            assert_ok!(DMS::migrate_contract(Origin::signed(1), 3, migration_signature(1, 3, 1), false));
+        });
+    }
+
+    #[test]
+    fn migrate_contract_should_keep_existential_deposit() {
+        with_externalities(&mut build_ext_with_existential_deposit(10), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
+
+            let signature = migration_signature(1, 3, 0);
+            assert_ok!(DMS::migrate_contract(Origin::signed(1), 3, signature, true));
 
+            // check that the old account is not reaped, so the contract and its escrow moved intact
+            assert_eq!(Balances::free_balance(&1), 10);
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert_eq!(DMS::contract(0).trustor, 3);
+            assert_eq!(DMS::contracts_count(3), 1);
+            assert_eq!(DMS::escrow(0), 20);
+            assert_eq!(Balances::free_balance(&3), 20);
+            assert_eq!(Balances::reserved_balance(&3), 20);
+        });
+    }
+
+    #[test]
+    fn migrate_contract_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            let signature = migration_signature(1, 3, 0);
             assert_noop!(
-                DMS::act_as_trustor(Origin::signed(3), 1, call.clone()),
-                "You are not the beneficiary for this trustor"
+                DMS::migrate_contract(Origin::signed(1), 3, signature.clone(), false),
+                "You do not have a current contract"
             );
 
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
+
             assert_noop!(
-                DMS::act_as_trustor(Origin::signed(2), 1, call),
-                "You cannot act as this trustor yet"
+                DMS::migrate_contract(Origin::signed(1), 1, signature.clone(), false),
+                "You cannot migrate to your own account"
+            );
+            assert_noop!(
+                DMS::migrate_contract(Origin::signed(1), 3, TestSignature(1, Vec::new()), false),
+                "Your migration signature is invalid"
+            );
+            assert_noop!(
+                DMS::migrate_contract(Origin::signed(1), 2, migration_signature(1, 2, 0), false),
+                "Your new account is the beneficiary of one of your contracts"
+            );
+            assert_noop!(
+                DMS::migrate_contract(Origin::signed(1), 3, signature.clone(), false),
+                "Your new account cannot cover the escrow of your contracts"
             );
 
-            System::set_block_number(11);
-            let call = BalancesCall::transfer(2, 51);
+            assert_ok!(DMS::add_ping_delegate(Origin::signed(1), 5));
             assert_noop!(
-                DMS::act_as_trustor(Origin::signed(2), 1, call),
-                "balance too low to send value"
+                DMS::migrate_contract(Origin::signed(1), 3, signature.clone(), true),
+                "Remove your ping delegates before migrating"
+            );
+            assert_ok!(DMS::remove_ping_delegate(Origin::signed(1), 5));
+
+            assert_ok!(DMS::set_duress_commitment(Origin::signed(1), Some(H256::from([7; 32]))));
+            assert_noop!(
+                DMS::migrate_contract(Origin::signed(1), 3, signature, true),
+                "Remove your duress commitment before migrating"
             );
         });
     }
 
     #[test]
-    fn create_contract_should_work() {
+    fn recovery_should_work() {
         with_externalities(&mut build_ext(), || {
-            // create a contract to give access to account #2 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10));
+            assert_ok!(DMS::set_recovery_config(Origin::signed(1), vec![3, 4, 5], 2, 10));
 
-            let contract = DMS::contract(1);
-            assert_eq!(contract.block_delay, 10);
-            assert_eq!(contract.execution_block, 11);
+            assert_ok!(DMS::initiate_recovery(Origin::signed(3), 1, 6));
+            assert_ok!(DMS::approve_recovery(Origin::signed(4), 1));
 
-            // check that account #2 has one trustor
-            assert_eq!(DMS::trustors_count(2), 1);
+            assert_noop!(
+                DMS::complete_recovery(Origin::signed(6), 1),
+                "The delay of this recovery is not over"
+            );
 
-            // check that account #1 does not have a trustor
-            assert_eq!(DMS::trustors_count(1), 0);
+            System::set_block_number(12);
+            assert_ok!(DMS::complete_recovery(Origin::signed(6), 1));
+            assert_eq!(DMS::recovery_controller(1), Some(6));
+            assert_eq!(DMS::active_recovery(1), None);
 
-            // check that account #1 is trustor of account #2
-            assert_eq!(DMS::trustors_by_index((2, 0)), 1);
+            assert_ok!(DMS::act_as_recovered(Origin::signed(6), 1, BalancesCall::transfer(6, 20)));
+            assert_eq!(Balances::free_balance(&6), 20);
         });
     }
 
     #[test]
-    fn create_contract_should_fail() {
+    fn recovery_should_fail() {
         with_externalities(&mut build_ext(), || {
-            // create a contract to give access to account #1 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(0), 1, 10));
+            assert_noop!(
+                DMS::set_recovery_config(Origin::signed(1), vec![3, 3], 1, 10),
+                "You named a friend more than once"
+            );
+            assert_noop!(
+                DMS::set_recovery_config(Origin::signed(1), vec![3, 4], 3, 10),
+                "Your recovery threshold is invalid"
+            );
+            assert_noop!(
+                DMS::initiate_recovery(Origin::signed(3), 1, 6),
+                "This account cannot be recovered"
+            );
 
-            // check that account cannot create another contract
+            assert_ok!(DMS::set_recovery_config(Origin::signed(1), vec![3, 4, 5], 2, 10));
             assert_noop!(
-                DMS::create_contract(Origin::signed(0), 2, 10),
-                "You can only have one contract"
+                DMS::initiate_recovery(Origin::signed(6), 1, 6),
+                "You are not a friend of this account"
             );
 
-            // check that short delay is disallowed
+            assert_ok!(DMS::initiate_recovery(Origin::signed(3), 1, 6));
             assert_noop!(
-                DMS::create_contract(Origin::signed(1), 2, 0),
-                "Your block delay is too short"
+                DMS::approve_recovery(Origin::signed(3), 1),
+                "You have already approved this recovery"
             );
 
-            // check that long delay is disallowed
+            System::set_block_number(12);
             assert_noop!(
-                DMS::create_contract(Origin::signed(1), 2, 1_000_000_001),
-                "Your block delay is too long"
+                DMS::complete_recovery(Origin::signed(6), 1),
+                "This recovery does not have enough approvals"
             );
 
-            // check that account cannot set themselves as beneficiary
+            // check that the account can cancel the recovery with its current key
+            assert_ok!(DMS::cancel_recovery(Origin::signed(1)));
             assert_noop!(
-                DMS::create_contract(Origin::signed(1), 1, 0),
-                "You cannot use yourself as your beneficiary"
+                DMS::approve_recovery(Origin::signed(4), 1),
+                "This account is not being recovered"
+            );
+            assert_noop!(
+                DMS::act_as_recovered(Origin::signed(6), 1, BalancesCall::transfer(6, 20)),
+                "You are not the controller of this account"
             );
         });
     }
 
     #[test]
-    fn delete_contract_should_work() {
+    fn revoke_recovery_should_work() {
         with_externalities(&mut build_ext(), || {
             // create a contract to give access to account #2 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::set_recovery_config(Origin::signed(1), vec![3], 1, 0));
+            assert_ok!(DMS::initiate_recovery(Origin::signed(3), 1, 6));
 
-            assert_ok!(DMS::delete_contract(Origin::signed(1)));
-            assert_eq!(<Contracts<Test>>::exists(1), false);
+            // check that the new controller can keep the account's contracts from firing
+            System::set_block_number(5);
+            assert_ok!(DMS::complete_recovery(Origin::signed(6), 1));
+            assert_eq!(DMS::is_ping_delegate((1, 6)), true);
+            assert_ok!(DMS::ping_alive_for(Origin::signed(6), 1));
+            assert_eq!(DMS::contract(0).execution_block, 15);
 
-            // check that account #2 does not have a trustor
-            assert_eq!(DMS::trustors_count(2), 0);
+            assert_ok!(DMS::revoke_recovery(Origin::signed(1), 1));
+            assert_eq!(DMS::recovery_controller(1), None);
+            assert_eq!(DMS::is_ping_delegate((1, 6)), false);
+            assert_eq!(DMS::ping_delegates_count(1), 0);
+            assert_noop!(
+                DMS::act_as_recovered(Origin::signed(6), 1, BalancesCall::transfer(6, 20)),
+                "You are not the controller of this account"
+            );
 
-            // check that indices are cleaned up
-            assert_eq!(DMS::trustor_index(1), 0);
-            assert_eq!(DMS::trustors_by_index((2, 0)), 0);
+            // check that root can revoke a controller too
+            assert_ok!(DMS::initiate_recovery(Origin::signed(3), 1, 7));
+            System::set_block_number(6);
+            assert_ok!(DMS::complete_recovery(Origin::signed(7), 1));
+            assert_ok!(DMS::force_revoke_recovery(Origin::ROOT, 1));
+            assert_eq!(DMS::recovery_controller(1), None);
+            assert_eq!(DMS::is_ping_delegate((1, 7)), false);
         });
     }
 
     #[test]
-    fn delete_contract_should_fail() {
+    fn revoke_recovery_should_fail() {
         with_externalities(&mut build_ext(), || {
             assert_noop!(
-                DMS::delete_contract(Origin::signed(1)),
-                "You do not have a current contract"
+                DMS::revoke_recovery(Origin::signed(1), 1),
+                "This account was not recovered"
+            );
+
+            assert_ok!(DMS::set_recovery_config(Origin::signed(1), vec![3], 1, 0));
+            assert_ok!(DMS::initiate_recovery(Origin::signed(3), 1, 6));
+            System::set_block_number(5);
+            assert_ok!(DMS::complete_recovery(Origin::signed(6), 1));
+
+            assert_noop!(
+                DMS::revoke_recovery(Origin::signed(3), 1),
+                "You are not the account or the controller of this recovery"
+            );
+            assert_noop!(
+                DMS::force_revoke_recovery(Origin::signed(1), 1),
+                "bad origin: expected to be a root origin"
             );
         });
     }
 
     #[test]
-    fn update_beneficiary_should_work() {
+    fn on_free_balance_zero_should_work() {
         with_externalities(&mut build_ext(), || {
-            // create contracts to give access to account #1 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10));
-            assert_ok!(DMS::create_contract(Origin::signed(20), 1, 10));
+            // create contracts between account #1, #2 and #3
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 3, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(3), 1, 10, CallScope::All));
 
-            // update beneficiary from account #1 to account #2
-            assert_ok!(DMS::update_beneficiary(Origin::signed(20), 2));
+            // check that the reaped account's contracts and list of trustors are removed
+            DMS::on_free_balance_zero(&1);
+            assert_eq!(DMS::contracts_count(1), 0);
+            assert_eq!(<Contracts<Test>>::exists(0), false);
+            assert_eq!(<Contracts<Test>>::exists(1), false);
+            assert_eq!(DMS::trustors_count(2), 0);
+            assert_eq!(DMS::trustors_count(3), 0);
+            assert_eq!(DMS::trustors_count(1), 0);
+            assert_eq!(<TrustorsIndex<Test>>::exists(2), false);
 
-            // check that account #2 has a trustor
-            assert_eq!(DMS::trustors_count(2), 1);
+            // check that the trustor of a reaped beneficiary can pick a new one
+            assert_eq!(DMS::contract(2).beneficiary, 1);
+            assert_ok!(DMS::update_beneficiary(Origin::signed(3), 2, 4));
+            assert_eq!(DMS::trustors_by_index((4, 0)), 2);
+        });
+    }
 
-            // check that account #1 only has one trustor
-            assert_eq!(DMS::trustors_count(1), 1);
+    #[test]
+    fn on_free_balance_zero_should_keep_handed_over_contracts() {
+        with_externalities(&mut build_ext_with_existential_deposit(10), || {
+            // create contracts to give access to accounts #2 and #3 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 3, 10, CallScope::All));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 20));
 
-            // check that account #20 is a trustor of account #2
-            assert_eq!(DMS::trustors_by_index((2, 0)), 20);
+            System::set_block_number(11);
+            take_over(0);
 
-            // check that account #10 is a trustor of account #1
-            assert_eq!(DMS::trustors_by_index((1, 0)), 10);
+            // check that nothing is cleaned up while the escrow is still reserved
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 30)));
+            assert_eq!(Balances::free_balance(&1), 0);
+            assert_eq!(DMS::contracts_count(1), 2);
+            assert_eq!(DMS::escrow(0), 20);
+            assert_ok!(DMS::withdraw_escrow(Origin::signed(2), 0, 2, 20));
+            assert_eq!(Balances::free_balance(&2), 150);
+        });
+
+        with_externalities(&mut build_ext_with_existential_deposit(10), || {
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 3, 10, CallScope::All));
+
+            System::set_block_number(11);
+            take_over(0);
+
+            // check that only the contract which is not being taken over is deleted
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 50)));
+            assert_eq!(<Contracts<Test>>::exists(0), true);
+            assert_eq!(DMS::takeover(0).is_some(), true);
+            assert_eq!(<Contracts<Test>>::exists(1), false);
         });
     }
 
     #[test]
-    fn update_beneficiary_should_fail() {
+    fn transitive_inheritance_should_work() {
         with_externalities(&mut build_ext(), || {
-            // create contracts to give access to account #1 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10));
-            assert_ok!(DMS::create_contract(Origin::signed(20), 1, 10));
+            // create a chain of contracts from account #1 to #2 to #3
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(2), 3, 20, CallScope::All));
+            assert_ok!(DMS::update_inheritance(Origin::signed(1), 0, true));
+            assert_ok!(DMS::set_takeover_parameters(Origin::ROOT, 0, 5));
 
-            // check that the updated beneficiary needs to be different
+            // check that the heir has to wait for the beneficiary's contract to be executable
+            System::set_block_number(11);
             assert_noop!(
-                DMS::update_beneficiary(Origin::signed(20), 1),
-                "Your beneficiary is already set to this account"
+                DMS::initiate_takeover(Origin::signed(3), 0),
+                "You are not the beneficiary for this trustor"
             );
 
-            // check that trustors without beneficiaries cannot update
+            System::set_block_number(21);
+            assert_ok!(DMS::initiate_takeover(Origin::signed(3), 0));
+
+            System::set_block_number(27);
+            assert_ok!(DMS::act_as_trustor(Origin::signed(3), 0, BalancesCall::transfer(3, 10)));
+            assert_eq!(Balances::free_balance(&3), 10);
+        });
+    }
+
+    #[test]
+    fn transitive_inheritance_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a cycle of contracts between account #1 and #2, and a contract from #2 to #3
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(2), 1, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(2), 3, 10, CallScope::All));
+            assert_ok!(DMS::update_inheritance(Origin::signed(2), 1, true));
+
+            System::set_block_number(11);
+
+            // check that only transitive contracts can be inherited
             assert_noop!(
-                DMS::update_beneficiary(Origin::signed(30), 1),
-                "You do not have a current contract"
+                DMS::act_as_trustor(Origin::signed(3), 0, BalancesCall::transfer(3, 10)),
+                "You are not the beneficiary for this trustor"
             );
 
-            // check that beneficiaries cannot be set to be the same as the trustor
+            // check that the search for heirs ends despite the cycle
+            assert_ok!(DMS::update_inheritance(Origin::signed(1), 0, true));
             assert_noop!(
-                DMS::update_beneficiary(Origin::signed(10), 10),
-                "You cannot use yourself as your beneficiary"
+                DMS::initiate_takeover(Origin::signed(4), 0),
+                "You are not the beneficiary for this trustor"
             );
         });
     }
 
     #[test]
-    fn update_block_delay_should_work() {
+    fn allowed_destinations_should_work() {
         with_externalities(&mut build_ext(), || {
-            // create contract to give access to account #1 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10));
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::add_allowed_destination(Origin::signed(1), 0, 5));
+            assert_ok!(DMS::add_allowed_destination(Origin::signed(1), 0, 6));
+            assert_ok!(DMS::remove_allowed_destination(Origin::signed(1), 0, 6));
 
-            // update block delay from 10 to 20
-            assert_ok!(DMS::update_block_delay(Origin::signed(10), 20));
+            System::set_block_number(11);
+            take_over(0);
 
-            let contract = DMS::contract(10);
-            assert_eq!(contract.block_delay, 20);
-            assert_eq!(contract.execution_block, 21);
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)),
+                "This call does not transfer to an allowed destination"
+            );
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(6, 10)),
+                "This call does not transfer to an allowed destination"
+            );
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(5, 10)));
+            assert_eq!(Balances::free_balance(&5), 10);
         });
     }
 
     #[test]
-    fn update_block_delay_should_fail() {
+    fn allowed_destinations_should_restrict_payouts() {
         with_externalities(&mut build_ext(), || {
-            // check that trustors without beneficiaries cannot update block delay
+            // create an escrow contract and a vesting contract which only pay out to account #5
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::update_handover(Origin::signed(1), 1, Handover::Vesting(10)));
+            assert_ok!(DMS::add_allowed_destination(Origin::signed(1), 0, 5));
+            assert_ok!(DMS::add_allowed_destination(Origin::signed(1), 1, 5));
+            assert_ok!(DMS::deposit_escrow(Origin::signed(1), 0, 10));
+
+            System::set_block_number(21);
             assert_noop!(
-                DMS::update_block_delay(Origin::signed(10), 10),
-                "You do not have a current contract"
+                DMS::claim_vested(Origin::signed(2), 1, 2),
+                "This payout does not go to an allowed destination"
             );
+            assert_ok!(DMS::claim_vested(Origin::signed(2), 1, 5));
+            assert_eq!(Balances::free_balance(&5), 40);
 
-            // create contract to give access to account #1 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10));
+            take_over(0);
+            assert_noop!(
+                DMS::withdraw_escrow(Origin::signed(2), 0, 2, 10),
+                "This payout does not go to an allowed destination"
+            );
+            assert_ok!(DMS::withdraw_escrow(Origin::signed(2), 0, 5, 10));
+            assert_eq!(Balances::free_balance(&5), 50);
+        });
+    }
+
+    #[test]
+    fn allowed_destinations_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
 
-            // check that short delay is disallowed
             assert_noop!(
-                DMS::update_block_delay(Origin::signed(10), 0),
-                "Your block delay is too short"
+                DMS::add_allowed_destination(Origin::signed(2), 0, 5),
+                "You are not the trustor for this contract"
+            );
+            assert_noop!(
+                DMS::remove_allowed_destination(Origin::signed(1), 0, 5),
+                "This destination is not allowed"
             );
 
-            // check that long delay is disallowed
+            assert_ok!(DMS::add_allowed_destination(Origin::signed(1), 0, 5));
             assert_noop!(
-                DMS::update_block_delay(Origin::signed(10), 1_000_000_001),
-                "Your block delay is too long"
+                DMS::add_allowed_destination(Origin::signed(1), 0, 5),
+                "This destination is already allowed"
             );
         });
     }
 
     #[test]
-    fn ping_alive_should_work() {
+    fn claim_with_secret_should_work() {
         with_externalities(&mut build_ext(), || {
-            // create contract to give access to account #1 after 10 blocks of inactivity
-            assert_ok!(DMS::create_contract(Origin::signed(10), 1, 10));
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            let secret = H256::from([7; 32]);
+            assert_ok!(DMS::update_claim_commitment(Origin::signed(1), 0, Some(BlakeTwo256::hash_of(&secret))));
 
-            System::set_block_number(2);
+            System::set_block_number(11);
+            take_over(0);
 
-            assert_ok!(DMS::ping_alive(Origin::signed(10)));
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)),
+                "You have not revealed the claim secret of this contract"
+            );
+            assert_ok!(DMS::claim_with_secret(Origin::signed(2), 0, secret));
+            assert_ok!(DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)));
 
-            let contract = DMS::contract(10);
-            assert_eq!(contract.execution_block, 12);
+            assert_eq!(DMS::contract(0).claim_commitment, None);
+
+            // check that the revealed secret cannot be replayed once the trustor pings alive
+            assert_ok!(DMS::ping_alive(Origin::signed(1)));
+            System::set_block_number(System::block_number() + 11);
+            assert_noop!(
+                DMS::claim_with_secret(Origin::signed(2), 0, secret),
+                "This contract does not have a claim secret"
+            );
+
+            let new_secret = H256::from([8; 32]);
+            assert_ok!(DMS::update_claim_commitment(Origin::signed(1), 0, Some(BlakeTwo256::hash_of(&new_secret))));
+            assert_err!(
+                DMS::claim_with_secret(Origin::signed(2), 0, secret),
+                "Your claim secret is invalid"
+            );
+            assert_noop!(
+                DMS::act_as_trustor(Origin::signed(2), 0, BalancesCall::transfer(2, 10)),
+                "You have not revealed the claim secret of this contract"
+            );
         });
     }
 
     #[test]
-    fn ping_alive_should_fail() {
+    fn claim_with_secret_should_fail() {
         with_externalities(&mut build_ext(), || {
-            // check that trustors without beneficiaries cannot ping
+            // create a contract to give access to account #2 after 10 blocks of inactivity
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            assert_ok!(DMS::create_contract(Origin::signed(1), 2, 10, CallScope::All));
+            let secret = H256::from([7; 32]);
+            assert_ok!(DMS::update_claim_commitment(Origin::signed(1), 0, Some(BlakeTwo256::hash_of(&secret))));
+
             assert_noop!(
-                DMS::ping_alive(Origin::signed(10)),
-                "You do not have a current contract"
+                DMS::claim_with_secret(Origin::signed(2), 0, secret),
+                "You cannot act as this trustor yet"
+            );
+
+            System::set_block_number(11);
+            assert_noop!(
+                DMS::claim_with_secret(Origin::signed(3), 0, secret),
+                "You are not the beneficiary for this trustor"
+            );
+            assert_noop!(
+                DMS::claim_with_secret(Origin::signed(2), 1, secret),
+                "This contract does not have a claim secret"
+            );
+
+            // check that wrong guesses lock out further guesses for longer each time
+            assert_err!(
+                DMS::claim_with_secret(Origin::signed(2), 0, H256::from([1; 32])),
+                "Your claim secret is invalid"
+            );
+            assert_eq!(DMS::failed_claims(0), (1, 111));
+            assert_noop!(
+                DMS::claim_with_secret(Origin::signed(2), 0, secret),
+                "You must wait before guessing the claim secret again"
+            );
+
+            System::set_block_number(111);
+            assert_err!(
+                DMS::claim_with_secret(Origin::signed(2), 0, H256::from([1; 32])),
+                "Your claim secret is invalid"
+            );
+            assert_eq!(DMS::failed_claims(0), (2, 311));
+
+            System::set_block_number(311);
+            assert_ok!(DMS::claim_with_secret(Origin::signed(2), 0, secret));
+            assert_eq!(DMS::failed_claims(0), (0, 0));
+            assert_noop!(
+                DMS::claim_with_secret(Origin::signed(2), 0, secret),
+                "This contract does not have a claim secret"
             );
         });
     }
@@ -60,6 +60,12 @@ pub type Nonce = u64;
 /// Module implementing dead man's switch account takeover
 pub mod dead_mans_switch;
 
+/// Module implementing a dead man's switch over the sudo key
+pub mod sudo_switch;
+
+/// Module implementing a dead man's switch over each consensus authority
+pub mod authority_switch;
+
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
 /// of data like extrinsics, allowing for them to continue syncing the network through upgrades
@@ -176,7 +182,7 @@ impl balances::Trait for Runtime {
     /// The type for recording an account's balance.
     type Balance = u128;
     /// What to do if an account's free balance gets zeroed.
-    type OnFreeBalanceZero = ();
+    type OnFreeBalanceZero = (PersonalSwitch, InstitutionalSwitch);
     /// What to do if a new account is created.
     type OnNewAccount = Indices;
     /// The uniquitous event type.
@@ -193,8 +199,51 @@ impl sudo::Trait for Runtime {
     type Proposal = Call;
 }
 
-impl dead_mans_switch::Trait for Runtime {
+impl sudo_switch::Trait for Runtime {
+    type Event = Event;
+}
+
+/// Finds the author of the current block from its aura slot.
+pub struct AuraAuthor;
+
+impl authority_switch::FindAuthor<AuthorityId> for AuraAuthor {
+    fn find_author() -> Option<AuthorityId> {
+        let authorities = Consensus::authorities();
+        let slot_duration = Aura::slot_duration();
+        if authorities.is_empty() || slot_duration == 0 {
+            return None;
+        }
+
+        let slot = Timestamp::now() / slot_duration;
+        authorities.get((slot % authorities.len() as u64) as usize).cloned()
+    }
+}
+
+impl authority_switch::Trait for Runtime {
+    type Event = Event;
+    type FindAuthor = AuraAuthor;
+}
+
+/// The switch for personal accounts, with short delays.
+impl dead_mans_switch::Trait<dead_mans_switch::Instance1> for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type TrustorCall = BalancesCall<Runtime>;
+    type PingFee = Balances;
+    type Signature = AccountSignature;
+    type OnSwitchFired = ();
+    type OnContractChanged = ();
+}
+
+/// The switch for institutional accounts, with long delays.
+impl dead_mans_switch::Trait<dead_mans_switch::Instance2> for Runtime {
     type Event = Event;
+    type Currency = Balances;
+    type TrustorCall = BalancesCall<Runtime>;
+    type PingFee = Balances;
+    type Signature = AccountSignature;
+    type OnSwitchFired = ();
+    type OnContractChanged = ();
 }
 
 construct_runtime!(
@@ -210,7 +259,10 @@ construct_runtime!(
 		Indices: indices,
 		Balances: balances,
 		Sudo: sudo,
-		DeadMansSwitchModule: dead_mans_switch::{Module, Call, Storage, Event<T>},
+		SudoSwitch: sudo_switch::{Module, Call, Storage, Event<T>, Config<T>},
+		AuthoritySwitch: authority_switch::{Module, Call, Storage, Event<T>, Config<T>},
+		PersonalSwitch: dead_mans_switch::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
+		InstitutionalSwitch: dead_mans_switch::<Instance2>::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -282,7 +334,51 @@ impl_runtime_apis! {
 
     impl runtime_api::TaggedTransactionQueue<Block> for Runtime {
         fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-            Executive::validate_transaction(tx)
+            // Liveness proofs are the only unsigned transactions accepted into the pool
+            if tx.signature.is_none() {
+                match tx.function {
+                    Call::PersonalSwitch(ref call) => return PersonalSwitch::validate_unsigned(call),
+                    Call::InstitutionalSwitch(ref call) => return InstitutionalSwitch::validate_unsigned(call),
+                    _ => {}
+                }
+            }
+
+            // Only senders who can actually ping for someone are prioritized, so that the pool
+            // cannot be flooded with top priority pings
+            let sender = match tx.signature {
+                Some((ref address, ..)) => Indices::lookup(address.clone()).ok(),
+                None => None,
+            };
+            let is_liveness_call = match (sender, &tx.function) {
+                (Some(sender), Call::PersonalSwitch(call)) => PersonalSwitch::is_liveness_call(&sender, call),
+                (Some(sender), Call::InstitutionalSwitch(call)) => InstitutionalSwitch::is_liveness_call(&sender, call),
+                (Some(sender), Call::SudoSwitch(sudo_switch::Call::ping_alive())) => sender == Sudo::key(),
+                _ => false,
+            };
+
+            let validity = Executive::validate_transaction(tx);
+            if !is_liveness_call {
+                return validity;
+            }
+
+            // A censored or delayed ping could cost a trustor their account
+            match validity {
+                TransactionValidity::Valid { requires, provides, .. } => TransactionValidity::Valid {
+                    priority: dead_mans_switch::LIVENESS_PRIORITY,
+                    requires,
+                    provides,
+                    longevity: dead_mans_switch::LIVENESS_LONGEVITY,
+                },
+                validity => validity,
+            }
+        }
+    }
+
+    impl dead_mans_switch::DeadMansSwitchApi<Block, AccountId, BlockNumber, Hash> for Runtime {
+        fn audit_log(trustor: AccountId) -> Vec<(u8, dead_mans_switch::AuditEntry<AccountId, BlockNumber, Hash>)> {
+            let personal = PersonalSwitch::audit_log(&trustor).into_iter().map(|entry| (1, entry));
+            let institutional = InstitutionalSwitch::audit_log(&trustor).into_iter().map(|entry| (2, entry));
+            personal.chain(institutional).collect()
         }
     }
 
@@ -0,0 +1,294 @@
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, StaticLookup, Zero};
+use support::dispatch::{Dispatchable, Result};
+use support::{decl_event, decl_module, decl_storage, ensure, StorageValue};
+use system::{ensure_root, ensure_signed, RawOrigin};
+
+/// A dead man's switch over the sudo key. The holder of the key must ping alive every
+/// `BlockDelay` blocks, otherwise the next of their `Successors` can claim the key.
+///
+/// The first successor can claim the key once the switch fires, the second one `BlockDelay`
+/// blocks later, and so on, so that the chain can still be upgraded when a successor is gone too.
+pub trait Trait: sudo::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber
+    {
+        SudoPingedAlive(BlockNumber),
+        SuccessorsUpdated,
+        SudoBlockDelayUpdated(BlockNumber),
+        SudoKeyClaimed(AccountId, AccountId),
+    }
+);
+
+decl_storage! {
+    trait Store for Module<T: Trait> as SudoSwitch {
+        /// The accounts which can claim the sudo key in turn once the switch fires
+        Successors get(successors) config(): Vec<T::AccountId>;
+
+        /// The number of blocks the sudo key holder can go without pinging alive
+        BlockDelay get(block_delay) config(): T::BlockNumber;
+
+        /// The block number at which the first successor can claim the sudo key
+        ExecutionBlock get(execution_block) build(|config: &GenesisConfig<T>| config.block_delay.clone()): T::BlockNumber;
+
+        /// The sudo key holder the switch is counting down for
+        LastHolder get(last_holder): Option<T::AccountId>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+        fn deposit_event<T>() = default;
+
+        fn on_finalize(n: T::BlockNumber) {
+            Self::restart_for_new_holder(n);
+        }
+
+        /// This call allows the sudo key holder to push back the `execution_block` of the switch.
+        pub fn ping_alive(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(sender == <sudo::Module<T>>::key(), "You are not the sudo key holder");
+
+            let execution_block = <system::Module<T>>::block_number() + Self::block_delay();
+            <ExecutionBlock<T>>::put(execution_block);
+
+            Self::deposit_event(RawEvent::SudoPingedAlive(execution_block));
+
+            Ok(())
+        }
+
+        /// This call allows root to set the accounts which can claim the sudo key in turn.
+        pub fn set_successors(origin, successors: Vec<T::AccountId>) -> Result {
+            ensure_root(origin)?;
+
+            <Successors<T>>::put(successors);
+
+            Self::deposit_event(RawEvent::SuccessorsUpdated);
+
+            Ok(())
+        }
+
+        /// This call allows root to set the number of blocks the sudo key holder can go without
+        /// pinging alive. The switch is pushed back accordingly.
+        pub fn set_block_delay(origin, block_delay: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(!block_delay.is_zero(), "Your block delay is too short");
+
+            <BlockDelay<T>>::put(block_delay);
+            <ExecutionBlock<T>>::put(<system::Module<T>>::block_number() + block_delay);
+
+            Self::deposit_event(RawEvent::SudoBlockDelayUpdated(block_delay));
+
+            Ok(())
+        }
+
+        /// This call allows a successor to claim the sudo key once the switch has fired and every
+        /// successor before them had their turn.
+        ///
+        /// The key is handed over through `sudo::set_key` on behalf of the current holder. The
+        /// claiming successor and those before them are removed from the list, and the switch
+        /// restarts for the new holder.
+        pub fn claim_sudo_key(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let mut successors = Self::successors();
+            let position = successors.iter().position(|successor| successor == &sender)
+                .ok_or("You are not a successor of the sudo key")?;
+
+            let current_block = <system::Module<T>>::block_number();
+            let claim_block = Self::execution_block() + Self::block_delay() * T::BlockNumber::sa(position as u64);
+            ensure!(claim_block <= current_block, "You cannot claim the sudo key yet");
+
+            let prev_key = <sudo::Module<T>>::key();
+            sudo::Call::<T>::set_key(T::Lookup::unlookup(sender.clone()))
+                .dispatch(RawOrigin::Signed(prev_key.clone()).into())?;
+
+            successors.drain(..=position);
+            <Successors<T>>::put(successors);
+            <ExecutionBlock<T>>::put(current_block + Self::block_delay());
+            <LastHolder<T>>::put(&sender);
+
+            Self::deposit_event(RawEvent::SudoKeyClaimed(prev_key, sender));
+
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Restarts the switch when the sudo key was handed over by other means than
+    /// `claim_sudo_key`, so that the new holder does not inherit the window of the previous one.
+    fn restart_for_new_holder(n: T::BlockNumber) {
+        let key = <sudo::Module<T>>::key();
+        match Self::last_holder() {
+            Some(ref holder) if holder == &key => {}
+            Some(_) => {
+                let execution_block = n + Self::block_delay();
+                <ExecutionBlock<T>>::put(execution_block);
+                <LastHolder<T>>::put(key);
+
+                Self::deposit_event(RawEvent::SudoPingedAlive(execution_block));
+            }
+            // the holder at genesis is counted down from the genesis configuration
+            None => <LastHolder<T>>::put(key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use primitives::{Blake2Hasher, H256};
+    use runtime_io::with_externalities;
+    use runtime_primitives::{
+        testing::{Digest, DigestItem, Header},
+        traits::{BlakeTwo256, IdentityLookup, OnFinalize},
+        BuildStorage,
+    };
+    use support::{assert_noop, assert_ok, impl_outer_origin};
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type TransferPayment = ();
+        type DustRemoval = ();
+    }
+
+    impl sudo::Trait for Test {
+        type Event = ();
+        type Proposal = balances::Call<Test>;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type SudoSwitch = Module<Test>;
+    type Sudo = sudo::Module<Test>;
+    type System = system::Module<Test>;
+
+    fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .0;
+        t.extend(sudo::GenesisConfig::<Test> { key: 1 }.build_storage().unwrap().0);
+        t.extend(
+            GenesisConfig::<Test> {
+                successors: vec![2, 3],
+                block_delay: 10,
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.into()
+    }
+
+    #[test]
+    fn ping_alive_should_work() {
+        with_externalities(&mut build_ext(), || {
+            assert_eq!(SudoSwitch::execution_block(), 10);
+
+            System::set_block_number(5);
+            assert_ok!(SudoSwitch::ping_alive(Origin::signed(1)));
+            assert_eq!(SudoSwitch::execution_block(), 15);
+
+            assert_noop!(SudoSwitch::ping_alive(Origin::signed(2)), "You are not the sudo key holder");
+        });
+    }
+
+    #[test]
+    fn claim_sudo_key_should_work() {
+        with_externalities(&mut build_ext(), || {
+            // check that the second successor can claim the key when the first one is gone too
+            System::set_block_number(20);
+            assert_ok!(SudoSwitch::claim_sudo_key(Origin::signed(3)));
+
+            assert_eq!(Sudo::key(), 3);
+            assert_eq!(SudoSwitch::successors(), Vec::<u64>::new());
+            assert_eq!(SudoSwitch::execution_block(), 30);
+        });
+    }
+
+    #[test]
+    fn set_key_should_restart_switch() {
+        with_externalities(&mut build_ext(), || {
+            System::set_block_number(1);
+            SudoSwitch::on_finalize(1);
+            assert_eq!(SudoSwitch::last_holder(), Some(1));
+            assert_eq!(SudoSwitch::execution_block(), 10);
+
+            // check that a new holder does not inherit the window of the previous one
+            System::set_block_number(9);
+            assert_ok!(Sudo::set_key(Origin::signed(1), 4));
+            SudoSwitch::on_finalize(9);
+            assert_eq!(SudoSwitch::last_holder(), Some(4));
+            assert_eq!(SudoSwitch::execution_block(), 19);
+
+            System::set_block_number(10);
+            assert_noop!(
+                SudoSwitch::claim_sudo_key(Origin::signed(2)),
+                "You cannot claim the sudo key yet"
+            );
+            SudoSwitch::on_finalize(10);
+            assert_eq!(SudoSwitch::execution_block(), 19);
+        });
+    }
+
+    #[test]
+    fn claim_sudo_key_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_noop!(
+                SudoSwitch::claim_sudo_key(Origin::signed(4)),
+                "You are not a successor of the sudo key"
+            );
+            assert_noop!(
+                SudoSwitch::claim_sudo_key(Origin::signed(2)),
+                "You cannot claim the sudo key yet"
+            );
+
+            System::set_block_number(10);
+            assert_noop!(
+                SudoSwitch::claim_sudo_key(Origin::signed(3)),
+                "You cannot claim the sudo key yet"
+            );
+            assert_ok!(SudoSwitch::claim_sudo_key(Origin::signed(2)));
+            assert_eq!(Sudo::key(), 2);
+            assert_eq!(SudoSwitch::successors(), vec![3]);
+        });
+    }
+}
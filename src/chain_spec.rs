@@ -1,7 +1,7 @@
 use primitives::{ed25519, sr25519, Pair};
 use dead_mans_switch_runtime::{
 	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
-	SudoConfig, IndicesConfig,
+	SudoConfig, SudoSwitchConfig, AuthoritySwitchConfig, IndicesConfig, PersonalSwitchConfig, InstitutionalSwitchConfig,
 };
 use substrate_service;
 
@@ -48,7 +48,8 @@ impl Alternative {
 				], vec![
 					account_key("Alice")
 				],
-					account_key("Alice")
+					account_key("Alice"),
+					vec![],
 				),
 				vec![],
 				None,
@@ -71,6 +72,7 @@ impl Alternative {
 					account_key("Ferdie"),
 				],
 					account_key("Alice"),
+					vec![],
 				),
 				vec![],
 				None,
@@ -90,7 +92,12 @@ impl Alternative {
 	}
 }
 
-fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<AccountId>, root_key: AccountId) -> GenesisConfig {
+fn testnet_genesis(
+	initial_authorities: Vec<AuthorityId>,
+	endowed_accounts: Vec<AccountId>,
+	root_key: AccountId,
+	authority_backups: Vec<(AuthorityId, AuthorityId)>,
+) -> GenesisConfig {
 	GenesisConfig {
 		consensus: Some(ConsensusConfig {
 			code: include_bytes!("../runtime/wasm/target/wasm32-unknown-unknown/release/dead_mans_switch_runtime_wasm.compact.wasm").to_vec(),
@@ -115,5 +122,23 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		authority_switch: Some(AuthoritySwitchConfig {
+			backups: authority_backups,
+			max_missed_blocks: 360, // 1 hour of 10 second blocks.
+		}),
+		sudo_switch: Some(SudoSwitchConfig {
+			successors: vec![account_key("Bob")],
+			block_delay: 120_960, // 2 weeks of 10 second blocks.
+		}),
+		dead_mans_switch_Instance1: Some(PersonalSwitchConfig {
+			min_block_delay: 10,
+			max_block_delay: 1_000_000,
+			phantom: Default::default(),
+		}),
+		dead_mans_switch_Instance2: Some(InstitutionalSwitchConfig {
+			min_block_delay: 100_000,
+			max_block_delay: 1_000_000_000,
+			phantom: Default::default(),
+		}),
 	}
 }